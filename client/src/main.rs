@@ -17,6 +17,9 @@ const ICON_BYTES: &[u8] = include_bytes!("../ec-su_axb35-win.png");
 const COG_ICON_BYTES: &[u8] = include_bytes!("../cog.png");
 const CHECK_ICON_BYTES: &[u8] = include_bytes!("../check.png");
 
+// Fixed level changes are reverted by the server unless confirmed within this time
+const CONFIRM_TIMEOUT_SECONDS: u64 = 15;
+
 // Configuration structure
 #[derive(Serialize, Deserialize, Clone)]
 struct Config {
@@ -67,11 +70,29 @@ struct PowerModeRequest {
 #[derive(Serialize, Debug)]
 struct FanModeRequest {
     mode: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confirm_timeout_seconds: Option<u64>,
 }
 
 #[derive(Serialize, Debug)]
 struct FanLevelRequest {
     level: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confirm_timeout_seconds: Option<u64>,
+}
+
+// Returned by write endpoints when the change is pending confirmation
+#[derive(Deserialize, Debug)]
+struct ChangeResponse {
+    change_id: Option<u64>,
+}
+
+// Applied changes waiting for the user to keep or revert them
+#[derive(Clone, Debug)]
+struct PendingChanges {
+    fan_id: i32,
+    change_ids: Vec<u64>,
+    deadline: Instant,
 }
 
 #[derive(Serialize, Debug)]
@@ -224,6 +245,7 @@ struct AppState {
     error_timestamp: Option<Instant>,
     color_thresholds: ColorThresholds,
     edit_state: EditState,
+    pending_changes: Option<PendingChanges>,
     cog_icon: Option<egui::TextureHandle>,
     check_icon: Option<egui::TextureHandle>,
     chart_data: ChartData,
@@ -241,6 +263,7 @@ impl AppState {
             error_timestamp: None,
             color_thresholds: ColorThresholds::default(),
             edit_state: EditState::default(),
+            pending_changes: None,
             cog_icon: None,
             check_icon: None,
             chart_data: ChartData::new(),
@@ -346,6 +369,16 @@ impl AppState {
         self.error_timestamp = Some(Instant::now());
     }
 
+    fn clear_expired_pending_changes(&mut self) {
+        if let Some(pending) = &self.pending_changes {
+            if Instant::now() >= pending.deadline {
+                let fan_id = pending.fan_id;
+                self.pending_changes = None;
+                self.set_error(format!("Fan{} settings were not confirmed and have been reverted", fan_id));
+            }
+        }
+    }
+
     fn clear_old_error(&mut self) {
         if let (Some(_), Some(timestamp)) = (&self.error_message, self.error_timestamp) {
             if timestamp.elapsed() > Duration::from_secs(5) {
@@ -646,6 +679,38 @@ impl EcMonitorApp {
         }
     }
 
+    // Confirms pending changes, or reverts them right away instead of waiting for the timeout
+    fn resolve_pending_changes(&self, change_ids: Vec<u64>, keep: bool) {
+        let state_clone = Arc::clone(&self.state);
+        tokio::spawn(async move {
+            let (client, server_url) = {
+                let state_guard = state_clone.lock().unwrap();
+                (state_guard.http_client.clone(), state_guard.server_url())
+            };
+
+            // Revert in reverse order so the fan mode is restored last
+            let ordered: Vec<u64> = if keep { change_ids } else { change_ids.into_iter().rev().collect() };
+            for change_id in ordered {
+                let result = if keep {
+                    client.post(format!("{}/changes/{}/confirm", server_url, change_id)).send().await
+                } else {
+                    client.delete(format!("{}/overrides/{}", server_url, change_id)).send().await
+                };
+
+                let action = if keep { "confirm" } else { "revert" };
+                let error = match result {
+                    Ok(response) if response.status().is_success() => None,
+                    Ok(response) => Some(format!("Failed to {} change: {}", action, response.status())),
+                    Err(e) => Some(format!("Failed to {} change: {}", action, e)),
+                };
+                if let Some(msg) = error {
+                    state_clone.lock().unwrap().set_error(msg);
+                    break;
+                }
+            }
+        });
+    }
+
     fn draw_fan_block_with_edit(&self, ui: &mut egui::Ui, fan_name: &str, fan_id: i32, fan: &FanMetrics, state: &mut AppState) {
         // Clone chart data and determine edit mode before the closure to avoid borrow issues
         let (history_clone, max_rpm) = match fan_id {
@@ -727,15 +792,24 @@ impl EcMonitorApp {
                                         let mut success = true;
                                         let mut error_msg = None;
                                         
+                                        // Fixed levels can turn the fan off, so they need to be confirmed
+                                        let confirm_timeout_seconds = if mode == "fixed" { Some(CONFIRM_TIMEOUT_SECONDS) } else { None };
+                                        let mut change_ids = Vec::new();
+                                        
                                         // Set fan mode
                                         if success {
                                             let url = format!("{}/fan{}/mode", server_url, fan_id);
                                             let request = FanModeRequest {
                                                 mode: mode.clone(),
+                                                confirm_timeout_seconds,
                                             };
                                             
                                             match client.post(&url).json(&request).send().await {
-                                                Ok(response) if response.status().is_success() => {},
+                                                Ok(response) if response.status().is_success() => {
+                                                    if let Ok(change) = response.json::<ChangeResponse>().await {
+                                                        change_ids.extend(change.change_id);
+                                                    }
+                                                },
                                                 Ok(response) => {
                                                     success = false;
                                                     error_msg = Some(format!("Failed to set fan mode: {}", response.status()));
@@ -750,10 +824,14 @@ impl EcMonitorApp {
                                         // Set level if in fixed mode and previous call succeeded
                                         if success && mode == "fixed" {
                                             let url = format!("{}/fan{}/level", server_url, fan_id);
-                                            let request = FanLevelRequest { level };
+                                            let request = FanLevelRequest { level, confirm_timeout_seconds };
                                             
                                             match client.post(&url).json(&request).send().await {
-                                                Ok(response) if response.status().is_success() => {},
+                                                Ok(response) if response.status().is_success() => {
+                                                    if let Ok(change) = response.json::<ChangeResponse>().await {
+                                                        change_ids.extend(change.change_id);
+                                                    }
+                                                },
                                                 Ok(response) => {
                                                     success = false;
                                                     error_msg = Some(format!("Failed to set fan level: {}", response.status()));
@@ -820,6 +898,16 @@ impl EcMonitorApp {
                                             }
                                         }
                                         
+                                        // Ask the user to keep or revert pending changes
+                                        if !change_ids.is_empty() {
+                                            let mut state_guard = state_clone.lock().unwrap();
+                                            state_guard.pending_changes = Some(PendingChanges {
+                                                fan_id,
+                                                change_ids,
+                                                deadline: Instant::now() + Duration::from_secs(CONFIRM_TIMEOUT_SECONDS),
+                                            });
+                                        }
+                                        
                                         // Update UI state
                                         if success {
                                             // Refresh metrics immediately after successful change
@@ -925,6 +1013,22 @@ impl EcMonitorApp {
                     });
                 });
                 
+                // Keep or revert prompt for pending changes
+                if let Some(pending) = state.pending_changes.clone().filter(|pending| pending.fan_id == fan_id) {
+                    let remaining = pending.deadline.saturating_duration_since(Instant::now()).as_secs();
+                    ui.horizontal(|ui| {
+                        ui.colored_label(egui::Color32::YELLOW, format!("Keep these settings? Reverting in {}s", remaining));
+                        if ui.button("Keep").clicked() {
+                            state.pending_changes = None;
+                            self.resolve_pending_changes(pending.change_ids.clone(), true);
+                        }
+                        if ui.button("Revert").clicked() {
+                            state.pending_changes = None;
+                            self.resolve_pending_changes(pending.change_ids.clone(), false);
+                        }
+                    });
+                }
+                
                 let is_edit_mode = match fan_id {
                     1 => state.edit_state.fan1_edit_mode,
                     2 => state.edit_state.fan2_edit_mode,
@@ -1096,6 +1200,7 @@ impl eframe::App for EcMonitorApp {
 
             // Clear old error messages
             state.clear_old_error();
+            state.clear_expired_pending_changes();
 
             // Track the starting position
             let start_y = ui.cursor().top();
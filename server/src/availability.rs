@@ -0,0 +1,95 @@
+// Whether the EC can be used. The API is served even while the driver or the EC can't be opened,
+// initialization is retried in the background until it succeeds. The same happens when port I/O
+// starts failing later on, then the device is reopened.
+
+use std::sync::{Arc, Mutex, OnceLock};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::ec::EcController;
+
+// Retry delays double after each failed attempt, up to the maximum
+const INITIAL_RETRY_SECONDS: u64 = 2;
+const MAX_RETRY_SECONDS: u64 = 60;
+
+pub fn retry_delay_seconds(attempts: u32) -> u64 {
+    let doublings = attempts.saturating_sub(1).min(16);
+    (INITIAL_RETRY_SECONDS << doublings).min(MAX_RETRY_SECONDS)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct EcStatus {
+    pub available: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,                 // Why the EC can't be used
+    pub attempts: u32,                         // Initialization or reconnect attempts so far
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_attempt: Option<DateTime<Utc>>,
+    pub reconnects: u32,                       // Times the device was reopened after I/O failures
+}
+
+#[derive(Default)]
+pub struct EcState {
+    controller: OnceLock<Arc<EcController>>,
+    status: Mutex<EcStatus>,
+}
+
+impl EcState {
+    // Only handed out while the EC is usable, not while a reconnect is in progress
+    pub fn controller(&self) -> Option<Arc<EcController>> {
+        if !self.is_available() {
+            return None;
+        }
+        self.controller.get().cloned()
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.status.lock().unwrap().available
+    }
+
+    pub fn status(&self) -> EcStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    pub fn set_unavailable(&self, error: String, attempts: u32, next_attempt: Option<DateTime<Utc>>) {
+        let mut status = self.status.lock().unwrap();
+        status.available = false;
+        status.error = Some(error);
+        status.attempts = attempts;
+        status.next_attempt = next_attempt;
+    }
+
+    pub fn set_available(&self, controller: Arc<EcController>) {
+        let _ = self.controller.set(controller);
+        let mut status = self.status.lock().unwrap();
+        status.available = true;
+        status.error = None;
+        status.next_attempt = None;
+    }
+
+    pub fn set_reconnected(&self) {
+        let mut status = self.status.lock().unwrap();
+        status.available = true;
+        status.error = None;
+        status.next_attempt = None;
+        status.reconnects += 1;
+    }
+
+    // Error for requests that need the EC
+    pub fn unavailable_error(&self) -> String {
+        match self.status.lock().unwrap().error {
+            Some(ref e) => format!("EC unavailable: {}", e),
+            None => "EC unavailable: initialization in progress".to_string(),
+        }
+    }
+}
+
+// Rejection for EC endpoints while the EC can't be used, turned into a 503 response
+#[derive(Debug)]
+pub struct EcUnavailable {
+    pub error: String,
+    pub ec: EcStatus,
+}
+
+impl warp::reject::Reject for EcUnavailable {}
@@ -1,13 +1,112 @@
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
 use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+use crate::config_overrides::{self, EffectiveSetting};
+use crate::curve::{self, FanCurveSet};
+use crate::ec::{FanMode, POWER_MODES};
+use crate::limits::FanLimits;
+use crate::logger::{LogFormat, LogLevels, LogRotation};
+use crate::migrations::{self, CURRENT_CONFIG_VERSION};
+use crate::pid::TargetSettings;
+use crate::presets;
+use crate::process_rules::ProcessRule;
+use crate::profiles::{self, FanProfile, Profile};
+use crate::reconcile::ReconcileSettings;
+use crate::restore::RestorePolicy;
+use crate::schedules::Schedule;
+use crate::sensors::TemperatureSource;
+
+// Environment variable with the config file location, --config takes precedence
+pub const CONFIG_PATH_ENV: &str = "EC_SERVER_CONFIG";
+
+static CONFIG_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+// Copies of the previous config files kept next to it, config.json.bak1 being the newest
+const CONFIG_BACKUPS: u32 = 5;
+
+// Content of the config file as the server last loaded or saved it, to tell its own writes from edits
+static KNOWN_CONTENT: Mutex<Option<String>> = Mutex::new(None);
+
+pub fn modified() -> Option<SystemTime> {
+    fs::metadata(config_path()).and_then(|metadata| metadata.modified()).ok()
+}
+
+// Default directory of config.json, the log file and the driver
+pub fn default_data_dir() -> PathBuf {
+    if cfg!(windows) {
+        let system_drive = std::env::var("SYSTEMDRIVE").unwrap_or_else(|_| "C:".to_string());
+        PathBuf::from(format!("{}\\", system_drive)).join("ProgramData").join("ec-su_axb35-win")
+    } else {
+        PathBuf::from("/var/lib/ec-su_axb35-win")
+    }
+}
+
+// Picks the config file from the command line, the environment or the default location. Only the first call
+// counts, later ones and config_path() get the same file.
+pub fn set_config_path(cli_path: Option<PathBuf>) -> &'static Path {
+    CONFIG_PATH.get_or_init(|| {
+        let path = cli_path
+            .or_else(|| std::env::var_os(CONFIG_PATH_ENV).filter(|p| !p.is_empty()).map(PathBuf::from))
+            .unwrap_or_else(|| default_data_dir().join("config.json"));
+        std::path::absolute(&path).unwrap_or(path)
+    })
+}
+
+pub fn config_path() -> &'static Path {
+    set_config_path(None)
+}
+
+// Relative paths in config are relative to the directory of the config file
+fn resolve_path(config_dir: &Path, path: &str) -> String {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return path.to_string_lossy().into_owned();
+    }
+    config_dir.join(path).to_string_lossy().into_owned()
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct FanConfig {
     pub mode: String,
     pub level: u8,
     pub rampup_curve: [u8; 5],
     pub rampdown_curve: [u8; 5],
+    #[serde(default)]
+    pub target: TargetSettings,
+    #[serde(default)]
+    pub temperature_source: TemperatureSource,
+    #[serde(default)]
+    pub power_mode_curves: BTreeMap<String, FanCurveSet>,
+    #[serde(flatten)]
+    pub limits: FanLimits,
+}
+
+impl FanConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        FanMode::from_str(&self.mode)
+            .ok_or_else(|| format!("Invalid fan mode: {}", self.mode))?;
+        if self.level > 5 {
+            return Err("Fan level must be 0-5".to_string());
+        }
+        curve::validate_curve(&self.rampup_curve)?;
+        curve::validate_curve(&self.rampdown_curve)?;
+        self.target.validate()?;
+        self.temperature_source.validate()?;
+        for (power_mode, set) in &self.power_mode_curves {
+            if !POWER_MODES.contains(&power_mode.as_str()) {
+                return Err(format!("Invalid power mode for curves: {}", power_mode));
+            }
+            curve::validate_curve(&set.rampup_curve)?;
+            curve::validate_curve(&set.rampdown_curve)?;
+        }
+        self.limits.validate()
+    }
 }
 
 impl Default for FanConfig {
@@ -15,107 +114,420 @@ impl Default for FanConfig {
         FanConfig {
             mode: "auto".to_string(),
             level: 0,
-            rampup_curve: [60, 70, 83, 95, 97],
-            rampdown_curve: [40, 50, 80, 94, 96],
+            rampup_curve: presets::LINUX_DEFAULT.rampup_curve,
+            rampdown_curve: presets::LINUX_DEFAULT.rampdown_curve,
+            target: TargetSettings::default(),
+            temperature_source: TemperatureSource::default(),
+            power_mode_curves: BTreeMap::new(),
+            limits: FanLimits::default(),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+fn backup_path(config_path: &Path, n: u32) -> PathBuf {
+    config_path.with_extension(format!("json.bak{}", n))
+}
+
+fn read_content(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))
+}
+
+fn parse_json(path: &Path, content: &str) -> Result<serde_json::Value, String> {
+    serde_json::from_str(content)
+        .map_err(|e| format!("Failed to parse config file {}: {}", path.display(), e))
+}
+
+// Reads a config file as JSON, failing if it's missing, unreadable or corrupt
+fn read_json(path: &Path) -> Result<serde_json::Value, String> {
+    parse_json(path, &read_content(path)?)
+}
+
+// Set if the config file couldn't be read and a backup was loaded instead
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConfigFallback {
+    pub error: String,     // Why the config file couldn't be used
+    pub backup: String,    // Backup that was loaded
+    pub corrupt: String,   // Copy of the unusable file
+}
+
+// What happened while loading the config file
+#[derive(Debug, Default)]
+pub struct LoadInfo {
+    pub migrated_from: Option<u32>,         // Version the file was upgraded from
+    pub backup: Option<PathBuf>,            // Copy of the file before the upgrade
+    pub fallback: Option<ConfigFallback>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct ServerConfig {
+    pub config_version: u32,
     pub host: String,
     pub port: u16,
     pub log_path: String,
+    #[serde(default)]
+    pub log_rotation: LogRotation,
+    #[serde(default)]
+    pub log_format: LogFormat,
+    #[serde(default)]
+    pub log_levels: LogLevels,
     pub driver_path: String,
     pub apu_power_mode: Option<String>,
+    #[serde(default)]
+    pub sensor_expiry_seconds: Option<u64>,
+    #[serde(default)]
+    pub curve_presets: BTreeMap<String, FanCurveSet>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    #[serde(default)]
+    pub schedules: Vec<Schedule>,
+    #[serde(default)]
+    pub process_rules: Vec<ProcessRule>,
+    #[serde(default)]
+    pub process_debounce_seconds: Option<u64>,
+    #[serde(default)]
+    pub restore_policy: RestorePolicy,
+    #[serde(default)]
+    pub reconcile: ReconcileSettings,
     pub fan1: Option<FanConfig>,
     pub fan2: Option<FanConfig>,
     pub fan3: Option<FanConfig>,
+    // What config.json has for the settings overridden on the command line or in the environment
+    #[serde(skip)]
+    pub overridden: BTreeMap<String, serde_json::Value>,
+    // What config.json has for the paths that are relative to the config directory
+    #[serde(skip)]
+    pub unresolved_paths: BTreeMap<String, String>,
 }
 
 impl Default for ServerConfig {
     fn default() -> Self {
-        let system_drive = std::env::var("SYSTEMDRIVE").unwrap_or_else(|_| "C:".to_string());
+        let data_dir = default_data_dir();
         
         // Fan3 has different default curves from Linux driver
-        let mut fan3_config = FanConfig::default();
-        fan3_config.rampup_curve = [20, 60, 83, 95, 97];
-        fan3_config.rampdown_curve = [0, 50, 80, 94, 96];
+        let fan3_config = FanConfig {
+            rampup_curve: presets::LINUX_FAN3_DEFAULT.rampup_curve,
+            rampdown_curve: presets::LINUX_FAN3_DEFAULT.rampdown_curve,
+            ..FanConfig::default()
+        };
         
         ServerConfig {
+            config_version: CURRENT_CONFIG_VERSION,
             host: "127.0.0.1".to_string(),
             port: 8395,
-            log_path: format!("{}\\ProgramData\\ec-su_axb35-win\\server.log", system_drive),
-            driver_path: format!("{}\\ProgramData\\ec-su_axb35-win\\winring0", system_drive),
+            log_path: data_dir.join("server.log").to_string_lossy().into_owned(),
+            log_rotation: LogRotation::default(),
+            log_format: LogFormat::default(),
+            log_levels: LogLevels::default(),
+            driver_path: data_dir.join("winring0").to_string_lossy().into_owned(),
             apu_power_mode: None,
+            sensor_expiry_seconds: None,
+            curve_presets: BTreeMap::new(),
+            profiles: BTreeMap::new(),
+            schedules: Vec::new(),
+            process_rules: Vec::new(),
+            process_debounce_seconds: None,
+            restore_policy: RestorePolicy::default(),
+            reconcile: ReconcileSettings::default(),
             fan1: Some(FanConfig::default()),
             fan2: Some(FanConfig::default()),
             fan3: Some(fan3_config),
+            overridden: BTreeMap::new(),
+            unresolved_paths: BTreeMap::new(),
         }
     }
 }
 
 impl ServerConfig {
-    pub fn load() -> Result<Self, String> {
-        let system_drive = std::env::var("SYSTEMDRIVE").unwrap_or_else(|_| "C:".to_string());
-        let config_path = format!("{}\\ProgramData\\ec-su_axb35-win\\config.json", system_drive);
+    // Saved settings of a fan, None if the fan isn't in the config
+    pub fn fan(&self, fan_id: u8) -> Option<&FanConfig> {
+        match fan_id {
+            1 => self.fan1.as_ref(),
+            2 => self.fan2.as_ref(),
+            3 => self.fan3.as_ref(),
+            _ => None,
+        }
+    }
+
+    // Power mode and fan settings saved in config, anything not saved is taken from the fallback
+    pub fn saved_profile(&self, fallback: &Profile) -> Profile {
+        let fan_profile = |fan_config: &Option<FanConfig>, fallback: &FanProfile| match fan_config {
+            Some(fan_config) => FanProfile {
+                mode: fan_config.mode.clone(),
+                level: fan_config.level,
+                rampup_curve: fan_config.rampup_curve,
+                rampdown_curve: fan_config.rampdown_curve,
+                limits: Some(fan_config.limits),
+            },
+            None => fallback.clone(),
+        };
+
+        Profile {
+            power_mode: self.apu_power_mode.clone().unwrap_or_else(|| fallback.power_mode.clone()),
+            fan1: fan_profile(&self.fan1, &fallback.fan1),
+            fan2: fan_profile(&self.fan2, &fallback.fan2),
+            fan3: fan_profile(&self.fan3, &fallback.fan3),
+        }
+    }
+
+    // Checks the values that are otherwise only checked when they arrive through the API
+    pub fn validate(&self) -> Result<(), String> {
+        self.host.parse::<std::net::IpAddr>()
+            .map_err(|_| format!("Invalid host address: {}", self.host))?;
+        self.log_rotation.validate()?;
+        self.log_levels.validate()?;
+        if let Some(ref power_mode) = self.apu_power_mode {
+            if !POWER_MODES.contains(&power_mode.as_str()) {
+                return Err(format!("Invalid power mode: {}", power_mode));
+            }
+        }
+        for (fan_idx, fan_config) in [&self.fan1, &self.fan2, &self.fan3].iter().enumerate() {
+            if let Some(fan_config) = fan_config {
+                fan_config.validate().map_err(|e| format!("Fan{}: {}", fan_idx + 1, e))?;
+            }
+        }
+        for (name, profile) in &self.profiles {
+            profiles::validate_profile_name(name)?;
+            profile.validate().map_err(|e| format!("Profile {}: {}", name, e))?;
+        }
+        for schedule in &self.schedules {
+            schedule.validate()
+                .and_then(|_| self.check_profile(&schedule.profile))
+                .map_err(|e| format!("Schedule {}-{}: {}", schedule.start, schedule.end, e))?;
+        }
+        for rule in &self.process_rules {
+            rule.validate()
+                .and_then(|_| self.check_profile(&rule.profile))
+                .map_err(|e| format!("Process rule for {}: {}", rule.profile, e))?;
+        }
+        Ok(())
+    }
+
+    pub fn check_profile(&self, name: &str) -> Result<(), String> {
+        if self.profiles.contains_key(name) {
+            Ok(())
+        } else {
+            Err(format!("Unknown profile: {}", name))
+        }
+    }
+
+    // Schedules and process rules that apply a profile, which keep it from being deleted
+    pub fn profile_users(&self, name: &str) -> Vec<String> {
+        let schedules = self.schedules.iter()
+            .filter(|schedule| schedule.profile == name)
+            .map(|schedule| format!("schedule {}-{}", schedule.start, schedule.end));
+        let process_rules = self.process_rules.iter()
+            .filter(|rule| rule.profile == name)
+            .map(|rule| format!("process rule for {}", rule.processes.join(", ")));
+        schedules.chain(process_rules).collect()
+    }
+
+    // Only checked for imports, which would fail halfway otherwise. A saved config may have such a level,
+    // the API changes the level and the limits one at a time.
+    pub fn validate_import(&self) -> Result<(), String> {
+        for (fan_idx, fan_config) in [&self.fan1, &self.fan2, &self.fan3].iter().enumerate() {
+            if let Some(fan_config) = fan_config.as_ref().filter(|fan_config| fan_config.mode == "fixed") {
+                fan_config.limits.check_rejected(fan_config.level)
+                    .map_err(|e| format!("Fan{}: {}", fan_idx + 1, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    // Makes the paths absolute in memory only, save() writes relative paths back the way the file has them
+    fn resolve_paths(&mut self, config_dir: &Path) {
+        for (setting, path) in [("log_path", &mut self.log_path), ("driver_path", &mut self.driver_path)] {
+            let resolved = resolve_path(config_dir, path);
+            if resolved != *path {
+                self.unresolved_paths.insert(setting.to_string(), std::mem::replace(path, resolved));
+            }
+        }
+    }
+    
+    pub fn load() -> Result<(Self, LoadInfo), String> {
+        let config_path = config_path();
+        let config_dir = config_path.parent().unwrap_or(Path::new(""));
+        let mut info = LoadInfo::default();
         
-        if !Path::new(&config_path).exists() {
+        if !config_path.exists() {
             // Create default config if it doesn't exist
-            let default_config = ServerConfig::default();
-            
-            // Create directory if it doesn't exist
-            let config_dir = Path::new(&config_path).parent().unwrap();
-            if !config_dir.exists() {
-                fs::create_dir_all(config_dir)
-                    .map_err(|e| format!("Failed to create config directory: {}", e))?;
+            ServerConfig::default().save()?;
+        }
+        
+        // A file cut short by a crash or that can't be used otherwise is replaced by the newest usable backup
+        let primary = read_content(config_path)
+            .and_then(|content| Self::load_candidate(config_path, &content).map(|candidate| (content, candidate)));
+        let (source, (mut config, migrated_from)) = match primary {
+            Ok((content, candidate)) => {
+                *KNOWN_CONTENT.lock().unwrap() = Some(content);
+                (config_path.to_path_buf(), candidate)
             }
-            
-            // Write default config
-            let config_json = serde_json::to_string_pretty(&default_config)
-                .map_err(|e| format!("Failed to serialize default config: {}", e))?;
-            
-            fs::write(&config_path, config_json)
-                .map_err(|e| format!("Failed to write default config: {}", e))?;
-            
-            return Ok(default_config);
+            Err(e) => {
+                let (backup, candidate) = (1..=CONFIG_BACKUPS)
+                    .map(|n| backup_path(config_path, n))
+                    .find_map(|backup| read_content(&backup)
+                        .and_then(|content| Self::load_candidate(&backup, &content))
+                        .ok()
+                        .map(|candidate| (backup, candidate)))
+                    .ok_or_else(|| format!("{}, and no usable backup was found", e))?;
+                
+                // The broken file is kept for a look, it's replaced on the next save
+                let corrupt = config_path.with_extension("json.corrupt");
+                fs::copy(config_path, &corrupt)
+                    .map_err(|e| format!("Failed to keep a copy of the corrupt config file: {}", e))?;
+                
+                info.fallback = Some(ConfigFallback {
+                    error: e,
+                    backup: backup.display().to_string(),
+                    corrupt: corrupt.display().to_string(),
+                });
+                (backup, candidate)
+            }
+        };
+        info.migrated_from = migrated_from;
+        
+        // Upgraded files are written back, the original is kept next to it
+        if let Some(version) = migrated_from {
+            let backup = config_path.with_extension(format!("v{}.json", version));
+            fs::copy(&source, &backup)
+                .map_err(|e| format!("Failed to back up config file to {}: {}", backup.display(), e))?;
+            config.save()?;
+            info.backup = Some(backup);
         }
         
-        let config_content = fs::read_to_string(&config_path)
-            .map_err(|e| format!("Failed to read config file {}: {}", config_path, e))?;
+        config.resolve_paths(config_dir);
+        
+        Ok((config, info))
+    }
+    
+    // Config file as load() uses it: upgraded in memory, with the overrides applied and validated, along with
+    // the version it was upgraded from. Anything that fails makes the file unusable.
+    fn load_candidate(path: &Path, content: &str) -> Result<(Self, Option<u32>), String> {
+        let mut value = parse_json(path, content)?;
+        let migrated_from = migrations::migrate(&mut value)
+            .map_err(|e| format!("Failed to upgrade config file {}: {}", path.display(), e))?;
+        let overridden = config_overrides::apply(&mut value);
         
-        let mut config: ServerConfig = serde_json::from_str(&config_content)
-            .map_err(|e| format!("Failed to parse config file: {}", e))?;
+        let mut config: ServerConfig = serde_json::from_value(value)
+            .map_err(|e| format!("Failed to parse config file {}: {}", path.display(), e))?;
+        config.overridden = overridden;
+        config.validate()
+            .map_err(|e| format!("Config file {} is invalid: {}", path.display(), e))?;
         
-        // Ensure paths are absolute
-        if !config.log_path.contains(':') {
-            config.log_path = format!("{}\\ProgramData\\ec-su_axb35-win\\{}", system_drive, config.log_path);
-        }
+        Ok((config, migrated_from))
+    }
+    
+    // Reads the config file again if it was changed by someone else than the server. The result is
+    // validated, an older version is upgraded in memory only.
+    pub fn reload() -> Result<Option<Self>, String> {
+        let config_path = config_path();
         
-        if !config.driver_path.contains(':') {
-            config.driver_path = format!("{}\\ProgramData\\ec-su_axb35-win\\{}", system_drive, config.driver_path);
+        let content = read_content(config_path)?;
+        if KNOWN_CONTENT.lock().unwrap().as_deref() == Some(content.as_str()) {
+            return Ok(None);
         }
         
+        let config = Self::from_json(parse_json(config_path, &content)?)
+            .map_err(|e| format!("Config file {}: {}", config_path.display(), e))?;
+        
+        *KNOWN_CONTENT.lock().unwrap() = Some(content);
+        Ok(Some(config))
+    }
+    
+    // Config from a reloaded file or an import: upgraded in memory if it's from an older version, validated,
+    // and with paths resolved like on load
+    pub fn from_json(mut value: serde_json::Value) -> Result<Self, String> {
+        let config_dir = config_path().parent().unwrap_or(Path::new(""));
+        
+        migrations::migrate(&mut value)?;
+        let overridden = config_overrides::apply(&mut value);
+        let mut config: ServerConfig = serde_json::from_value(value)
+            .map_err(|e| format!("Failed to parse config: {}", e))?;
+        config.overridden = overridden;
+        config.validate()?;
+        
+        config.resolve_paths(config_dir);
+        
         Ok(config)
     }
     
-    pub fn save(&self) -> Result<(), String> {
-        let system_drive = std::env::var("SYSTEMDRIVE").unwrap_or_else(|_| "C:".to_string());
-        let config_path = format!("{}\\ProgramData\\ec-su_axb35-win\\config.json", system_drive);
+    // Every setting with the value the server would run with and where it comes from. Unlike load() this
+    // never writes anything, a missing config file counts as the default config.
+    pub fn effective() -> Result<BTreeMap<String, EffectiveSetting>, String> {
+        let config_path = config_path();
+        let file = if config_path.exists() { Some(read_json(config_path)?) } else { None };
+        
+        let value = match file {
+            Some(ref value) => value.clone(),
+            None => serde_json::to_value(ServerConfig::default())
+                .map_err(|e| format!("Failed to serialize config: {}", e))?,
+        };
+        let config = Self::from_json(value)
+            .map_err(|e| format!("Config file {}: {}", config_path.display(), e))?;
+        
+        Ok(config_overrides::effective(&config, file.as_ref()))
+    }
+    
+    // Relative paths that still resolve to the path in use are saved as they were written
+    fn unresolve_paths(&self, value: &mut serde_json::Value, config_dir: &Path) {
+        if let Some(map) = value.as_object_mut() {
+            for (setting, path) in &self.unresolved_paths {
+                if map.get(setting).and_then(|value| value.as_str()) == Some(resolve_path(config_dir, path).as_str()) {
+                    map.insert(setting.clone(), path.clone().into());
+                }
+            }
+        }
+    }
+    
+    // Writes a temporary file and renames it over the config file, so a crash never leaves a half written
+    // config behind. The previous file is rotated into the backups first.
+    pub fn save(&mut self) -> Result<(), String> {
+        let config_path = config_path();
         
         // Create directory if it doesn't exist
-        let config_dir = Path::new(&config_path).parent().unwrap();
-        if !config_dir.exists() {
-            fs::create_dir_all(config_dir)
-                .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        if let Some(config_dir) = config_path.parent() {
+            if !config_dir.exists() {
+                fs::create_dir_all(config_dir)
+                    .map_err(|e| format!("Failed to create config directory: {}", e))?;
+            }
         }
         
-        // Serialize and write config
-        let config_json = serde_json::to_string_pretty(self)
-            .map_err(|e| format!("Failed to serialize config: {}", e))?;
+        // Serialize and write config, overridden settings and relative paths keep the value they have in the file
+        let config_json = if self.overridden.is_empty() && self.unresolved_paths.is_empty() {
+            serde_json::to_string_pretty(self)
+        } else {
+            serde_json::to_value(&*self).and_then(|mut value| {
+                self.unresolve_paths(&mut value, config_path.parent().unwrap_or(Path::new("")));
+                config_overrides::restore(&mut value, &mut self.overridden);
+                serde_json::to_string_pretty(&value)
+            })
+        }
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
         
-        fs::write(&config_path, config_json)
+        let temp_path = config_path.with_extension("json.tmp");
+        let mut file = fs::File::create(&temp_path)
+            .map_err(|e| format!("Failed to write config file: {}", e))?;
+        file.write_all(config_json.as_bytes())
+            .and_then(|_| file.sync_all())
             .map_err(|e| format!("Failed to write config file: {}", e))?;
+        drop(file);
+        *KNOWN_CONTENT.lock().unwrap() = Some(config_json);
+        
+        // A corrupt file isn't worth keeping as a backup
+        if read_json(config_path).is_ok() {
+            for n in (1..CONFIG_BACKUPS).rev() {
+                let backup = backup_path(config_path, n);
+                if backup.exists() {
+                    fs::rename(&backup, backup_path(config_path, n + 1))
+                        .map_err(|e| format!("Failed to rotate config backups: {}", e))?;
+                }
+            }
+            fs::copy(config_path, backup_path(config_path, 1))
+                .map_err(|e| format!("Failed to back up config file: {}", e))?;
+        }
+        
+        fs::rename(&temp_path, config_path)
+            .map_err(|e| format!("Failed to replace config file: {}", e))?;
         
         Ok(())
     }
@@ -0,0 +1,198 @@
+// Config settings given on the command line or in EC_SERVER_* environment variables. They win over
+// config.json (command line > environment > file > default) but are never written to it.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::config::ServerConfig;
+
+pub const ENV_PREFIX: &str = "EC_SERVER_";
+
+// config_version describes the file itself and can't be overridden
+const FIXED_SETTINGS: [&str; 1] = ["config_version"];
+
+// Relative paths given on the command line or in the environment are relative to the working directory
+const PATH_SETTINGS: [&str; 2] = ["log_path", "driver_path"];
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueSource {
+    Default,
+    File,
+    Env,
+    Cli,
+}
+
+#[derive(Debug, Clone)]
+struct Override {
+    value: Value,
+    source: ValueSource,
+}
+
+// Value a setting ends up with and where it comes from, for --print-effective-config
+#[derive(Debug, Serialize)]
+pub struct EffectiveSetting {
+    pub value: Value,
+    pub source: ValueSource,
+}
+
+static OVERRIDES: OnceLock<BTreeMap<String, Override>> = OnceLock::new();
+
+fn default_config() -> Map<String, Value> {
+    match serde_json::to_value(ServerConfig::default()) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+// Top level settings of config.json that can be overridden
+pub fn setting_names() -> Vec<String> {
+    default_config().into_iter()
+        .map(|(name, _)| name)
+        .filter(|name| !FIXED_SETTINGS.contains(&name.as_str()))
+        .collect()
+}
+
+pub fn env_name(setting: &str) -> String {
+    format!("{}{}", ENV_PREFIX, setting.to_uppercase())
+}
+
+fn describe(setting: &str, source: ValueSource) -> String {
+    match source {
+        ValueSource::Env => env_name(setting),
+        _ => "the command line".to_string(),
+    }
+}
+
+// String settings take the text as it is, anything else is parsed as JSON and falls back to a string,
+// e.g. 8395, null or {"interval_seconds": 30}
+fn parse_value(setting: &str, raw: &str, defaults: &Map<String, Value>) -> Value {
+    let value = match defaults.get(setting) {
+        Some(Value::String(_)) => Value::String(raw.to_string()),
+        _ => serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string())),
+    };
+
+    match value.as_str() {
+        Some(path) if PATH_SETTINGS.contains(&setting) => {
+            let path = PathBuf::from(path);
+            Value::String(std::path::absolute(&path).unwrap_or(path).to_string_lossy().into_owned())
+        }
+        _ => value,
+    }
+}
+
+// Collects the overrides from the command line, given as (setting, value) pairs, and the environment.
+// Each one is checked against the default config, so a bad value is reported before anything is loaded.
+// Only the first call counts.
+pub fn init(cli: Vec<(String, String)>) -> Result<(), String> {
+    let defaults = default_config();
+    let names = setting_names();
+    let mut overrides = BTreeMap::new();
+
+    for name in &names {
+        if let Some(raw) = std::env::var(env_name(name)).ok().filter(|raw| !raw.is_empty()) {
+            let value = parse_value(name, &raw, &defaults);
+            overrides.insert(name.clone(), Override { value, source: ValueSource::Env });
+        }
+    }
+    for (name, raw) in cli {
+        if !names.contains(&name) {
+            return Err(format!("Unknown config setting: {}", name));
+        }
+        let value = parse_value(&name, &raw, &defaults);
+        overrides.insert(name, Override { value, source: ValueSource::Cli });
+    }
+
+    // Kept the way the config serializes it, so restore() can tell whether the running value still is the override
+    for (name, o) in overrides.iter_mut() {
+        let mut config = defaults.clone();
+        config.insert(name.clone(), o.value.clone());
+        let config = serde_json::from_value::<ServerConfig>(Value::Object(config))
+            .map_err(|e| e.to_string())
+            .and_then(|config| config.validate().map(|_| config))
+            .map_err(|e| format!("Invalid value for {} from {}: {}", name, describe(name, o.source), e))?;
+        if let Ok(Value::Object(mut config)) = serde_json::to_value(config) {
+            if let Some(value) = config.remove(name.as_str()) {
+                o.value = value;
+            }
+        }
+    }
+
+    let _ = OVERRIDES.set(overrides);
+    Ok(())
+}
+
+fn overrides() -> &'static BTreeMap<String, Override> {
+    OVERRIDES.get_or_init(BTreeMap::new)
+}
+
+// Overridden settings with where their value comes from, for the startup log
+pub fn active() -> Vec<(String, String)> {
+    overrides().iter()
+        .map(|(name, o)| (name.clone(), describe(name, o.source)))
+        .collect()
+}
+
+// Puts the overrides into a config read from the file and returns the values they replaced, save()
+// writes those back instead of the overrides. A setting missing from the file replaced its default.
+pub fn apply(config: &mut Value) -> BTreeMap<String, Value> {
+    let mut replaced = BTreeMap::new();
+    if overrides().is_empty() {
+        return replaced;
+    }
+
+    let defaults = default_config();
+    if let Some(map) = config.as_object_mut() {
+        for (name, o) in overrides() {
+            let file_value = map.insert(name.clone(), o.value.clone())
+                .or_else(|| defaults.get(name).cloned())
+                .unwrap_or(Value::Null);
+            replaced.insert(name.clone(), file_value);
+        }
+    }
+    replaced
+}
+
+// Turns a config about to be saved back into what the file had for the overridden settings. A setting that
+// was changed while running, e.g. through the API, is saved with its new value instead, which becomes the
+// file value, but the override still wins on the next start.
+pub fn restore(config: &mut Value, replaced: &mut BTreeMap<String, Value>) {
+    if let Some(map) = config.as_object_mut() {
+        for (name, file_value) in replaced.iter_mut() {
+            let changed = match (map.get(name), overrides().get(name)) {
+                (Some(value), Some(o)) if *value != o.value && value != file_value => Some(value.clone()),
+                _ => None,
+            };
+            if let Some(value) = changed {
+                log::warn!("{} is overridden by {}, the change is saved to the config file but the override applies again on the next start",
+                    name, describe(name, overrides()[name].source));
+                *file_value = value;
+            }
+            map.insert(name.clone(), file_value.clone());
+        }
+    }
+}
+
+// Effective value and source of every setting, `file` is the content of config.json if it exists
+pub fn effective(config: &ServerConfig, file: Option<&Value>) -> BTreeMap<String, EffectiveSetting> {
+    let values = match serde_json::to_value(config) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    };
+
+    setting_names().into_iter()
+        .map(|name| {
+            let source = match overrides().get(&name) {
+                Some(o) => o.source,
+                None if file.and_then(|file| file.get(&name)).is_some() => ValueSource::File,
+                None => ValueSource::Default,
+            };
+            let value = values.get(&name).cloned().unwrap_or(Value::Null);
+            (name, EffectiveSetting { value, source })
+        })
+        .collect()
+}
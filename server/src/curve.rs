@@ -0,0 +1,138 @@
+// Curve engine logic shared by the live curve monitoring task and the dry-run simulation
+
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+use crate::limits::{Clamp, FanLimits};
+
+// The curve monitoring task ticks once per second, so one tick is one second of simulated time
+pub const CURVE_TICK_SECONDS: u64 = 1;
+
+// Upper bound for a single simulation run (7 days of simulated time)
+const MAX_SIMULATION_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+// Rampup/rampdown pair, used for the curves that replace the default ones in a given power mode
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq)]
+pub struct FanCurveSet {
+    pub rampup_curve: [u8; 5],
+    pub rampdown_curve: [u8; 5],
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CurveStep {
+    pub level: u8,
+    pub threshold: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct CurveSimulation {
+    pub levels: Vec<u8>,          // Level at the end of each input sample
+    pub clamps: Vec<Option<Clamp>>,  // How the fan's limits moved that level, if they did
+    pub transitions: u32,
+    pub time_at_level: [u64; 6],  // Seconds spent at levels 0-5
+}
+
+pub fn validate_curve(curve: &[u8; 5]) -> Result<(), String> {
+    // Validate curve values (0-100°C)
+    for &temp in curve {
+        if temp > 100 {
+            return Err("Temperature values must be 0-100°C".to_string());
+        }
+    }
+    Ok(())
+}
+
+// Level a fan starts at when it is switched to curve mode
+pub fn initial_level(temp: u8, rampup_curve: &[u8; 5]) -> u8 {
+    // Find appropriate level based on current temperature using rampup curve
+    for i in (1..=5).rev() {
+        if temp >= rampup_curve[i - 1] {
+            return i as u8;
+        }
+    }
+    0
+}
+
+// Single curve engine step, returns None if the fan should stay at its current level
+pub fn next_level(current_level: u8, temp: u8, rampup_curve: &[u8; 5], rampdown_curve: &[u8; 5]) -> Option<CurveStep> {
+    // Check if we should ramp up
+    if current_level < 5 && temp >= rampup_curve[current_level as usize] {
+        return Some(CurveStep {
+            level: current_level + 1,
+            threshold: rampup_curve[current_level as usize],
+        });
+    }
+
+    // Check if we should ramp down
+    if current_level > 0 && temp <= rampdown_curve[(current_level - 1) as usize] {
+        return Some(CurveStep {
+            level: current_level - 1,
+            threshold: rampdown_curve[(current_level - 1) as usize],
+        });
+    }
+
+    None
+}
+
+// Replay a temperature series through the curve engine, with each step moved into the fan's limits like the
+// engine does. Each sample is (temperature, seconds it was held) and is evaluated once per curve tick.
+pub fn simulate(
+    samples: &[(u8, u64)],
+    initial: Option<u8>,
+    rampup_curve: &[u8; 5],
+    rampdown_curve: &[u8; 5],
+    limits: &FanLimits,
+) -> Result<CurveSimulation, String> {
+    if samples.is_empty() {
+        return Err("Temperature series is empty".to_string());
+    }
+
+    let total_seconds = samples.iter()
+        .try_fold(0u64, |acc, &(_, duration)| acc.checked_add(duration))
+        .unwrap_or(u64::MAX);
+    if total_seconds > MAX_SIMULATION_SECONDS {
+        return Err(format!("Simulated time must not exceed {} seconds", MAX_SIMULATION_SECONDS));
+    }
+
+    let mut level = match initial {
+        Some(level) if level > 5 => return Err("Fan level must be 0-5".to_string()),
+        Some(level) => level,
+        None => initial_level(samples[0].0, rampup_curve),
+    };
+
+    let mut result = CurveSimulation {
+        levels: Vec::with_capacity(samples.len()),
+        clamps: Vec::with_capacity(samples.len()),
+        transitions: 0,
+        time_at_level: [0; 6],
+    };
+
+    for &(temp, duration) in samples {
+        let mut remaining = duration.max(CURVE_TICK_SECONDS);
+        let mut clamp = None;
+
+        while remaining > 0 {
+            // The level the curves pick, or the current one, is moved into the limits
+            let next = next_level(level, temp, rampup_curve, rampdown_curve).map_or(level, |step| step.level);
+            clamp = limits.clamp(next, Some(temp));
+            let next = clamp.as_ref().map_or(next, |clamp| clamp.level);
+
+            if next != level {
+                level = next;
+                result.transitions += 1;
+                let tick = remaining.min(CURVE_TICK_SECONDS);
+                result.time_at_level[level as usize] += tick;
+                remaining -= tick;
+            } else {
+                // Temperature is constant within a sample, so the level is settled
+                result.time_at_level[level as usize] += remaining;
+                remaining = 0;
+            }
+        }
+
+        result.levels.push(level);
+        result.clamps.push(clamp);
+    }
+
+    Ok(result)
+}
@@ -56,7 +56,7 @@ impl DriverManager {
             "WinRing0.sys"
         };
 
-        let driver_file_path = format!("{}\\{}", self.driver_path, driver_filename);
+        let driver_file_path = Path::new(&self.driver_path).join(driver_filename).to_string_lossy().into_owned();
 
         if !Path::new(&driver_file_path).exists() {
             return Err(format!("Driver file not found: {}", driver_file_path));
@@ -1,5 +1,8 @@
 use std::ptr;
 use std::ffi::CString;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::atomic::{AtomicU32, Ordering};
+use chrono::{DateTime, Utc};
 use winapi::um::winnt::{HANDLE, GENERIC_READ, GENERIC_WRITE};
 use winapi::um::fileapi::{CreateFileA, OPEN_EXISTING};
 use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
@@ -7,6 +10,14 @@ use winapi::um::ioapiset::DeviceIoControl;
 use winapi::um::minwinbase::OVERLAPPED;
 use winapi::um::errhandlingapi::GetLastError;
 
+use crate::curve::{self, FanCurveSet};
+use crate::limits::{Clamp, FanLimits, LimitViolation};
+use crate::pid::{self, PidController, TargetSettings};
+use crate::presets;
+use crate::profiles::{FanProfile, Profile};
+use crate::reconcile::{DriftEvent, DriftPolicy, ReconcileSettings};
+use crate::sensors::{PushedSensor, TemperatureSource};
+
 // WinRing0 driver constants
 const WINRING0_DEVICE_NAME: &str = "\\\\.\\WinRing0_1_2_0";
 const OLS_TYPE: u32 = 40000;
@@ -23,6 +34,9 @@ const EC_COMMAND_WRITE: u8 = 0x81;
 const RW_TIMEOUT: u32 = 500;
 const MAX_RETRIES: u32 = 5;
 
+// Port I/O failing this many times in a row means the driver handle is no longer usable
+const IO_FAILURE_THRESHOLD: u32 = 5;
+
 // EC Status flags
 const EC_STATUS_OUTPUT_BUFFER_FULL: u8 = 0x01;
 const EC_STATUS_INPUT_BUFFER_FULL: u8 = 0x02;
@@ -46,6 +60,13 @@ const EC_REG_FAN3_SPEED_HIGH: u8 = 0x28;
 const EC_REG_FAN3_SPEED_LOW: u8 = 0x29;
 const EC_REG_FAN3_MODE: u8 = 0x25;
 
+// APU power modes, indexed by their EC register value
+pub const POWER_MODES: [&str; 3] = ["balanced", "performance", "quiet"];
+
+// Recorded temperature samples of the APU and of each fan's source (one per second at most, so about an hour)
+const TEMPERATURE_HISTORY_LEN: usize = 3600;
+type TemperatureSamples = VecDeque<(DateTime<Utc>, u8)>;
+
 #[repr(C)]
 struct WriteIoPortInput {
     port_number: u32,
@@ -61,12 +82,29 @@ pub enum EcOperation {
     GetFanRpm(u8),
     GetFanMode(u8),
     SetFanMode(u8, String),
+    AdoptFanMode(u8, String),
     GetFanLevel(u8),
     SetFanLevel(u8, u8),
     GetFanRampupCurve(u8),
     SetFanRampupCurve(u8, [u8; 5]),
     GetFanRampdownCurve(u8),
     SetFanRampdownCurve(u8, [u8; 5]),
+    SetFanCurves(u8, FanCurveSet),
+    GetTemperatureHistory(u8),
+    GetFanTarget(u8),
+    SetFanTarget(u8, TargetSettings),
+    GetFanTemperatureSource(u8),
+    SetFanTemperatureSource(u8, TemperatureSource),
+    PushSensor(String, u8, u64),
+    GetSensors,
+    GetFanPowerModeCurves(u8),
+    SetFanPowerModeCurves(u8, BTreeMap<String, FanCurveSet>),
+    GetFanActiveCurves(u8),
+    GetFanLimits(u8),
+    SetFanLimits(u8, FanLimits),
+    GetProfileState,
+    ApplyProfile(Profile),
+    Reconcile(ReconcileSettings),
 }
 
 #[derive(Debug, Clone)]
@@ -77,8 +115,20 @@ pub enum EcResult {
     FanRpm(u16),
     FanMode(String),
     FanLevel(u8),
+    FanLevelSet { level: u8, clamp: Option<Clamp> },
     FanRampupCurve([u8; 5]),
     FanRampdownCurve([u8; 5]),
+    FanCurves(FanCurveSet),
+    TemperatureHistory(Vec<(DateTime<Utc>, u8)>),
+    FanTarget(TargetSettings),
+    FanTemperatureSource(TemperatureSource),
+    Sensor(String, PushedSensor),
+    Sensors(Vec<(String, PushedSensor)>),
+    FanPowerModeCurves(BTreeMap<String, FanCurveSet>),
+    FanActiveCurves { curve_set: Option<String>, curves: FanCurveSet },
+    FanLimits(FanLimits),
+    Profile(Profile),
+    Drift(Vec<DriftEvent>),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -86,6 +136,12 @@ pub struct FanCurveData {
     pub rampup_curve: [u8; 5],    // Temperature thresholds for levels 1-5
     pub rampdown_curve: [u8; 5],  // Temperature thresholds for levels 1-5
     pub mode: FanMode,            // Use enum instead of String for Copy trait
+    pub target: TargetSettings,   // Settings for target temperature mode
+    pub pid: PidController,       // Controller state for target temperature mode
+    pub power_mode_curves: [Option<FanCurveSet>; 3], // Curves replacing the default ones, indexed like POWER_MODES
+    pub limits: FanLimits,        // Safety limits for every level written in fixed, curve and target mode
+    pub mode_applied: bool,       // Mode was written by the server, so the register can be checked for drift
+    pub last_level: Option<u8>,   // Level last written by the server
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -93,6 +149,7 @@ pub enum FanMode {
     Auto,
     Fixed,
     Curve,
+    Target,
 }
 
 impl FanMode {
@@ -102,6 +159,7 @@ impl FanMode {
             FanMode::Auto => "auto",
             FanMode::Fixed => "fixed",
             FanMode::Curve => "curve",
+            FanMode::Target => "target",
         }
     }
     
@@ -110,6 +168,7 @@ impl FanMode {
             "auto" => Some(FanMode::Auto),
             "fixed" => Some(FanMode::Fixed),
             "curve" => Some(FanMode::Curve),
+            "target" => Some(FanMode::Target),
             _ => None,
         }
     }
@@ -118,20 +177,33 @@ impl FanMode {
 impl Default for FanCurveData {
     fn default() -> Self {
         FanCurveData {
-            rampup_curve: [60, 70, 83, 95, 97],   // Default from Linux driver
-            rampdown_curve: [40, 50, 80, 94, 96], // Default from Linux driver
+            rampup_curve: presets::LINUX_DEFAULT.rampup_curve,
+            rampdown_curve: presets::LINUX_DEFAULT.rampdown_curve,
             mode: FanMode::Auto,
+            target: TargetSettings::default(),
+            pid: PidController::default(),
+            power_mode_curves: [None; 3],
+            limits: FanLimits::default(),
+            mode_applied: false,
+            last_level: None,
         }
     }
 }
 
 pub struct EcController {
-    driver_handle: HANDLE,
+    driver_handle: std::sync::Mutex<HANDLE>,
+    io_failures: AtomicU32, // Port I/O failures in a row
     fan_curves: std::sync::Mutex<[FanCurveData; 3]>, // Data for fans 1, 2, 3
+    temperature_history: std::sync::Mutex<TemperatureSamples>,
+    fan_temperature_history: std::sync::Mutex<[TemperatureSamples; 3]>, // Fans with other sources than the APU
+    temperature_sources: std::sync::Mutex<[TemperatureSource; 3]>, // Curve/target input for fans 1, 2, 3
+    pushed_sensors: std::sync::Mutex<HashMap<String, PushedSensor>>,
+    power_mode: std::sync::Mutex<Option<u8>>, // Last power mode register value seen or written
+    desired_power_mode: std::sync::Mutex<Option<u8>>, // Power mode register value the reconciler expects
 }
 
 impl EcController {
-    pub fn new() -> Result<Self, String> {
+    fn open_device() -> Result<HANDLE, String> {
         let device_name = CString::new(WINRING0_DEVICE_NAME).unwrap();
 
         let handle = unsafe {
@@ -150,19 +222,57 @@ impl EcController {
             let error = unsafe { GetLastError() };
             return Err(format!("Failed to open WinRing0 driver. Error code: {}", error));
         }
+        Ok(handle)
+    }
+
+    pub fn new() -> Result<Self, String> {
+        let handle = Self::open_device()?;
 
-        // Initialize fan curves with defaults, but customize fan3
+        // Initialize fan curves with the per-fan defaults
         let mut curves = [FanCurveData::default(); 3];
-        // Fan3 has different default curves from Linux driver
-        curves[2].rampup_curve = [20, 60, 83, 95, 97];
-        curves[2].rampdown_curve = [0, 50, 80, 94, 96];
+        for (fan_idx, data) in curves.iter_mut().enumerate() {
+            let defaults = presets::default_curves(fan_idx as u8 + 1);
+            data.rampup_curve = defaults.rampup_curve;
+            data.rampdown_curve = defaults.rampdown_curve;
+        }
 
         Ok(EcController {
-            driver_handle: handle,
+            driver_handle: std::sync::Mutex::new(handle),
+            io_failures: AtomicU32::new(0),
             fan_curves: std::sync::Mutex::new(curves),
+            temperature_history: std::sync::Mutex::new(VecDeque::with_capacity(TEMPERATURE_HISTORY_LEN)),
+            fan_temperature_history: std::sync::Mutex::new(Default::default()),
+            temperature_sources: std::sync::Mutex::new(Default::default()),
+            pushed_sensors: std::sync::Mutex::new(HashMap::new()),
+            power_mode: std::sync::Mutex::new(None),
+            desired_power_mode: std::sync::Mutex::new(None),
         })
     }
 
+    // Whether port I/O keeps failing, e.g. because the driver was unloaded
+    pub fn io_failing(&self) -> bool {
+        self.io_failures.load(Ordering::Relaxed) >= IO_FAILURE_THRESHOLD
+    }
+
+    // Replaces the driver handle with a new one, the old one is closed first so the device can be opened again
+    pub fn reopen(&self) -> Result<(), String> {
+        let mut handle = self.driver_handle.lock().unwrap();
+        if *handle != INVALID_HANDLE_VALUE {
+            unsafe {
+                CloseHandle(*handle);
+            }
+            *handle = INVALID_HANDLE_VALUE;
+        }
+
+        *handle = Self::open_device()?;
+        self.io_failures.store(0, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn check_io(&self) -> Result<(), String> {
+        self.read_io_port(COMMAND_PORT).map(|_| ())
+    }
+
     pub async fn execute_operation(&self, operation: EcOperation) -> Result<EcResult, String> {
         match operation {
             EcOperation::GetFirmwareVersion => {
@@ -178,26 +288,21 @@ impl EcController {
             }
             EcOperation::GetApuPowerMode => {
                 let mode_val = self.read_byte(EC_REG_APU_POWER_MODE)?;
-                let mode = match mode_val {
-                    0x00 => "balanced",
-                    0x01 => "performance", 
-                    0x02 => "quiet",
-                    _ => return Err(format!("Unknown power mode: 0x{:02X}", mode_val)),
-                };
+                let mode = POWER_MODES.get(mode_val as usize)
+                    .ok_or_else(|| format!("Unknown power mode: 0x{:02X}", mode_val))?;
+                *self.power_mode.lock().unwrap() = Some(mode_val);
                 Ok(EcResult::ApuPowerMode(mode.to_string()))
             }
             EcOperation::SetApuPowerMode(mode) => {
-                let mode_val = match mode.as_str() {
-                    "balanced" => 0x00,
-                    "performance" => 0x01,
-                    "quiet" => 0x02,
-                    _ => return Err(format!("Invalid power mode: {}", mode)),
-                };
+                let mode_val = POWER_MODES.iter().position(|m| *m == mode)
+                    .ok_or_else(|| format!("Invalid power mode: {}", mode))? as u8;
                 self.write_byte(EC_REG_APU_POWER_MODE, mode_val)?;
+                *self.power_mode.lock().unwrap() = Some(mode_val);
+                *self.desired_power_mode.lock().unwrap() = Some(mode_val);
                 Ok(EcResult::ApuPowerMode(mode))
             }
             EcOperation::GetApuTemperature => {
-                let temp = self.read_byte(EC_REG_APU_TEMPERATURE)?;
+                let temp = self.read_apu_temperature()?;
                 Ok(EcResult::ApuTemperature(temp))
             }
             EcOperation::GetFanRpm(fan_id) => {
@@ -214,73 +319,29 @@ impl EcController {
                 Ok(EcResult::FanRpm(rpm))
             }
             EcOperation::GetFanMode(fan_id) => {
-                let mode_reg = self.get_fan_mode_register(fan_id)?;
-                let mode_val = self.read_byte(mode_reg)?;
-                
-                let curves = self.fan_curves.lock().unwrap();
-                let fan_idx = (fan_id - 1) as usize;
-                
-                let mode = match mode_val {
-                    0x10 | 0x20 | 0x30 => "auto",
-                    0x11 | 0x21 | 0x31 => {
-                        // Check stored mode to distinguish between fixed and curve
-                        if curves[fan_idx].mode == FanMode::Curve {
-                            "curve"
-                        } else {
-                            "fixed"
-                        }
-                    },
-                    _ => return Err(format!("Unknown fan mode: 0x{:02X}", mode_val)),
-                };
-                
+                let mode = self.read_fan_mode(fan_id)?;
                 Ok(EcResult::FanMode(mode.to_string()))
             }
             EcOperation::SetFanMode(fan_id, mode) => {
-                let mode_reg = self.get_fan_mode_register(fan_id)?;
-                let base_val = match fan_id {
-                    1 => 0x10,
-                    2 => 0x20,
-                    3 => 0x30,
-                    _ => return Err(format!("Invalid fan ID: {}", fan_id)),
-                };
-                
                 let fan_mode = FanMode::from_str(&mode)
                     .ok_or_else(|| format!("Invalid fan mode: {}", mode))?;
                 
-                let mode_val = match fan_mode {
-                    FanMode::Auto => base_val,
-                    FanMode::Fixed | FanMode::Curve => base_val + 1,
-                };
-                
-                // Update stored mode
-                {
-                    let mut curves = self.fan_curves.lock().unwrap();
-                    let fan_idx = (fan_id - 1) as usize;
-                    curves[fan_idx].mode = fan_mode;
-                }
-                
-                self.write_byte(mode_reg, mode_val)?;
-                
-                // When switching to curve mode, set initial fan level based on current temperature
-                if fan_mode == FanMode::Curve {
-                    if let Ok(temp) = self.read_byte(EC_REG_APU_TEMPERATURE) {
-                        let curves = self.fan_curves.lock().unwrap();
-                        let fan_idx = (fan_id - 1) as usize;
-                        let mut initial_level = 0;
-                        
-                        // Find appropriate level based on current temperature using rampup curve
-                        for i in (1..=5).rev() {
-                            if temp >= curves[fan_idx].rampup_curve[i - 1] {
-                                initial_level = i as u8;
-                                break;
-                            }
-                        }
-                        
-                        drop(curves); // Release lock before calling write_fan_level
-                        self.write_fan_level(fan_id, initial_level)?;
-                    }
+                self.set_fan_mode(fan_id, fan_mode)?;
+                Ok(EcResult::FanMode(mode))
+            }
+            EcOperation::AdoptFanMode(fan_id, mode) => {
+                if !(1..=3).contains(&fan_id) {
+                    return Err(format!("Invalid fan ID: {}", fan_id));
                 }
                 
+                let fan_mode = FanMode::from_str(&mode)
+                    .ok_or_else(|| format!("Invalid fan mode: {}", mode))?;
+                
+                // Takes over a mode the EC already has without writing it
+                let mut curves = self.fan_curves.lock().unwrap();
+                let fan_idx = (fan_id - 1) as usize;
+                curves[fan_idx].mode = fan_mode;
+                curves[fan_idx].mode_applied = true;
                 Ok(EcResult::FanMode(mode))
             }
             EcOperation::GetFanLevel(fan_id) => {
@@ -304,8 +365,17 @@ impl EcController {
                     return Err("Fan level must be 0-5".to_string());
                 }
                 
+                let limits = self.fan_limits(fan_id)?;
+                let clamp = self.clamp_level(fan_id, &limits, level);
+                if let Some(ref clamp) = clamp {
+                    if limits.limit_violation == LimitViolation::Reject {
+                        return Err(format!("Fan{} level {} rejected: {}", fan_id, level, clamp.reason));
+                    }
+                }
+                
+                let level = clamp.as_ref().map_or(level, |clamp| clamp.level);
                 self.write_fan_level(fan_id, level)?;
-                Ok(EcResult::FanLevel(level))
+                Ok(EcResult::FanLevelSet { level, clamp })
             }
             EcOperation::GetFanRampupCurve(fan_id) => {
                 if fan_id < 1 || fan_id > 3 {
@@ -321,12 +391,7 @@ impl EcController {
                     return Err(format!("Invalid fan ID: {}", fan_id));
                 }
                 
-                // Validate curve values (0-100°C)
-                for &temp in &curve {
-                    if temp > 100 {
-                        return Err("Temperature values must be 0-100°C".to_string());
-                    }
-                }
+                curve::validate_curve(&curve)?;
                 
                 let mut curves = self.fan_curves.lock().unwrap();
                 let fan_idx = (fan_id - 1) as usize;
@@ -347,19 +412,358 @@ impl EcController {
                     return Err(format!("Invalid fan ID: {}", fan_id));
                 }
                 
-                // Validate curve values (0-100°C)
-                for &temp in &curve {
-                    if temp > 100 {
-                        return Err("Temperature values must be 0-100°C".to_string());
-                    }
-                }
+                curve::validate_curve(&curve)?;
                 
                 let mut curves = self.fan_curves.lock().unwrap();
                 let fan_idx = (fan_id - 1) as usize;
                 curves[fan_idx].rampdown_curve = curve;
                 Ok(EcResult::FanRampdownCurve(curve))
             }
+            EcOperation::SetFanCurves(fan_id, set) => {
+                if !(1..=3).contains(&fan_id) {
+                    return Err(format!("Invalid fan ID: {}", fan_id));
+                }
+                
+                curve::validate_curve(&set.rampup_curve)?;
+                curve::validate_curve(&set.rampdown_curve)?;
+                
+                // Both curves change under one lock, so the curve engine never sees a mixed pair
+                let mut curves = self.fan_curves.lock().unwrap();
+                let fan_idx = (fan_id - 1) as usize;
+                curves[fan_idx].rampup_curve = set.rampup_curve;
+                curves[fan_idx].rampdown_curve = set.rampdown_curve;
+                Ok(EcResult::FanCurves(set))
+            }
+            EcOperation::GetFanTarget(fan_id) => {
+                if !(1..=3).contains(&fan_id) {
+                    return Err(format!("Invalid fan ID: {}", fan_id));
+                }
+                
+                let curves = self.fan_curves.lock().unwrap();
+                let fan_idx = (fan_id - 1) as usize;
+                Ok(EcResult::FanTarget(curves[fan_idx].target))
+            }
+            EcOperation::SetFanTarget(fan_id, settings) => {
+                if !(1..=3).contains(&fan_id) {
+                    return Err(format!("Invalid fan ID: {}", fan_id));
+                }
+                
+                settings.validate()?;
+                
+                let mut curves = self.fan_curves.lock().unwrap();
+                let fan_idx = (fan_id - 1) as usize;
+                curves[fan_idx].target = settings;
+                Ok(EcResult::FanTarget(settings))
+            }
+            EcOperation::GetFanTemperatureSource(fan_id) => {
+                if !(1..=3).contains(&fan_id) {
+                    return Err(format!("Invalid fan ID: {}", fan_id));
+                }
+                
+                let sources = self.temperature_sources.lock().unwrap();
+                let fan_idx = (fan_id - 1) as usize;
+                Ok(EcResult::FanTemperatureSource(sources[fan_idx].clone()))
+            }
+            EcOperation::SetFanTemperatureSource(fan_id, source) => {
+                if !(1..=3).contains(&fan_id) {
+                    return Err(format!("Invalid fan ID: {}", fan_id));
+                }
+                
+                source.validate()?;
+                
+                let mut sources = self.temperature_sources.lock().unwrap();
+                let fan_idx = (fan_id - 1) as usize;
+                // Samples of the previous source don't belong to the history of the new one
+                if sources[fan_idx] != source {
+                    self.fan_temperature_history.lock().unwrap()[fan_idx].clear();
+                }
+                sources[fan_idx] = source.clone();
+                Ok(EcResult::FanTemperatureSource(source))
+            }
+            EcOperation::PushSensor(name, temperature, expiry_seconds) => {
+                crate::sensors::validate_sensor_name(&name)?;
+                
+                if expiry_seconds > crate::sensors::MAX_SENSOR_EXPIRY_SECONDS {
+                    return Err(format!("Sensor expiry must not exceed {} seconds", crate::sensors::MAX_SENSOR_EXPIRY_SECONDS));
+                }
+                
+                let now = Utc::now();
+                let sensor = PushedSensor {
+                    temperature,
+                    updated: now,
+                    expires: now + chrono::Duration::seconds(expiry_seconds as i64),
+                };
+                
+                let mut sensors = self.pushed_sensors.lock().unwrap();
+                sensors.retain(|_, sensor| !sensor.is_forgotten(now));
+                if !sensors.contains_key(&name) && sensors.len() >= crate::sensors::MAX_PUSHED_SENSORS {
+                    return Err(format!("Too many pushed sensors, at most {} are kept", crate::sensors::MAX_PUSHED_SENSORS));
+                }
+                sensors.insert(name.clone(), sensor);
+                Ok(EcResult::Sensor(name, sensor))
+            }
+            EcOperation::GetSensors => {
+                let mut sensors = self.pushed_sensors.lock().unwrap();
+                let now = Utc::now();
+                sensors.retain(|_, sensor| !sensor.is_forgotten(now));
+                let mut list: Vec<(String, PushedSensor)> = sensors.iter()
+                    .map(|(name, sensor)| (name.clone(), *sensor))
+                    .collect();
+                list.sort_by(|a, b| a.0.cmp(&b.0));
+                Ok(EcResult::Sensors(list))
+            }
+            EcOperation::GetFanPowerModeCurves(fan_id) => {
+                if !(1..=3).contains(&fan_id) {
+                    return Err(format!("Invalid fan ID: {}", fan_id));
+                }
+                
+                let curves = self.fan_curves.lock().unwrap();
+                let fan_idx = (fan_id - 1) as usize;
+                let sets = POWER_MODES.iter()
+                    .zip(curves[fan_idx].power_mode_curves.iter())
+                    .filter_map(|(mode, set)| set.map(|set| (mode.to_string(), set)))
+                    .collect();
+                Ok(EcResult::FanPowerModeCurves(sets))
+            }
+            EcOperation::SetFanPowerModeCurves(fan_id, sets) => {
+                if !(1..=3).contains(&fan_id) {
+                    return Err(format!("Invalid fan ID: {}", fan_id));
+                }
+                
+                let mut power_mode_curves = [None; 3];
+                for (mode, set) in &sets {
+                    let mode_idx = POWER_MODES.iter().position(|m| m == mode)
+                        .ok_or_else(|| format!("Invalid power mode: {}", mode))?;
+                    curve::validate_curve(&set.rampup_curve)?;
+                    curve::validate_curve(&set.rampdown_curve)?;
+                    power_mode_curves[mode_idx] = Some(*set);
+                }
+                
+                let mut curves = self.fan_curves.lock().unwrap();
+                let fan_idx = (fan_id - 1) as usize;
+                curves[fan_idx].power_mode_curves = power_mode_curves;
+                Ok(EcResult::FanPowerModeCurves(sets))
+            }
+            EcOperation::GetFanActiveCurves(fan_id) => {
+                if !(1..=3).contains(&fan_id) {
+                    return Err(format!("Invalid fan ID: {}", fan_id));
+                }
+                
+                let curves = self.fan_curves.lock().unwrap();
+                let fan_idx = (fan_id - 1) as usize;
+                let (curve_set, curves) = self.active_curves(&curves[fan_idx]);
+                Ok(EcResult::FanActiveCurves {
+                    curve_set: curve_set.map(|mode| mode.to_string()),
+                    curves,
+                })
+            }
+            EcOperation::GetFanLimits(fan_id) => {
+                Ok(EcResult::FanLimits(self.fan_limits(fan_id)?))
+            }
+            EcOperation::SetFanLimits(fan_id, limits) => {
+                if !(1..=3).contains(&fan_id) {
+                    return Err(format!("Invalid fan ID: {}", fan_id));
+                }
+                
+                limits.validate()?;
+                
+                // Levels already set are moved into the new limits by the next enforce_fan_limits call
+                let mut curves = self.fan_curves.lock().unwrap();
+                let fan_idx = (fan_id - 1) as usize;
+                curves[fan_idx].limits = limits;
+                Ok(EcResult::FanLimits(limits))
+            }
+            EcOperation::GetProfileState => {
+                Ok(EcResult::Profile(self.profile_state()?))
+            }
+            EcOperation::ApplyProfile(profile) => {
+                // Validate everything up front, so a bad profile doesn't leave a half-applied state
+                profile.validate()?;
+                for (fan_idx, fan) in profile.fans().iter().enumerate() {
+                    let fan_id = fan_idx as u8 + 1;
+                    if fan.mode != "fixed" {
+                        continue;
+                    }
+                    let limits = match fan.limits {
+                        Some(limits) => limits,
+                        None => self.fan_limits(fan_id)?,
+                    };
+                    if let Some(clamp) = self.clamp_level(fan_id, &limits, fan.level) {
+                        if limits.limit_violation == LimitViolation::Reject {
+                            return Err(format!("Fan{} level {} rejected: {}", fan_id, fan.level, clamp.reason));
+                        }
+                    }
+                }
+                
+                // A write failing halfway through puts back the state from before
+                let previous = self.profile_state()?;
+                match self.write_profile(&profile) {
+                    Ok(applied) => Ok(EcResult::Profile(applied)),
+                    Err(e) => {
+                        if let Err(rollback_error) = self.write_profile(&previous) {
+                            log::error!(event = "profile_rollback_failed", error = rollback_error.as_str();
+                                "Failed to restore the state from before the profile: {}", rollback_error);
+                        }
+                        Err(e)
+                    }
+                }
+            }
+            EcOperation::Reconcile(settings) => {
+                Ok(EcResult::Drift(self.reconcile(&settings)?))
+            }
+            EcOperation::GetTemperatureHistory(fan_id) => {
+                if !(1..=3).contains(&fan_id) {
+                    return Err(format!("Invalid fan ID: {}", fan_id));
+                }
+                
+                // The history of the temperature that drives the fan
+                let fan_idx = (fan_id - 1) as usize;
+                let history = if self.temperature_sources.lock().unwrap()[fan_idx] == TemperatureSource::Apu {
+                    self.temperature_history.lock().unwrap().iter().copied().collect()
+                } else {
+                    self.fan_temperature_history.lock().unwrap()[fan_idx].iter().copied().collect()
+                };
+                Ok(EcResult::TemperatureHistory(history))
+            }
+        }
+    }
+
+    // Power mode and fan settings in the form of a profile
+    fn profile_state(&self) -> Result<Profile, String> {
+        let mode_val = self.read_byte(EC_REG_APU_POWER_MODE)?;
+        let power_mode = POWER_MODES.get(mode_val as usize)
+            .ok_or_else(|| format!("Unknown power mode: 0x{:02X}", mode_val))?;
+        *self.power_mode.lock().unwrap() = Some(mode_val);
+        
+        let mut fans = Vec::with_capacity(3);
+        for fan_id in 1..=3 {
+            let mode = self.read_fan_mode(fan_id)?;
+            let level = self.read_fan_level(fan_id)?;
+            let curves = self.fan_curves.lock().unwrap();
+            let fan_idx = (fan_id - 1) as usize;
+            fans.push(FanProfile {
+                mode: mode.to_string(),
+                level,
+                rampup_curve: curves[fan_idx].rampup_curve,
+                rampdown_curve: curves[fan_idx].rampdown_curve,
+                limits: Some(curves[fan_idx].limits),
+            });
+        }
+        
+        let [fan1, fan2, fan3]: [FanProfile; 3] = fans.try_into().unwrap();
+        Ok(Profile {
+            power_mode: power_mode.to_string(),
+            fan1,
+            fan2,
+            fan3,
+        })
+    }
+
+    // Returns the profile as written, with fixed levels moved into the fan's limits
+    fn write_profile(&self, profile: &Profile) -> Result<Profile, String> {
+        let mut applied = profile.clone();
+        let mode_val = POWER_MODES.iter().position(|m| *m == profile.power_mode)
+            .ok_or_else(|| format!("Invalid power mode: {}", profile.power_mode))? as u8;
+        self.write_byte(EC_REG_APU_POWER_MODE, mode_val)?;
+        *self.power_mode.lock().unwrap() = Some(mode_val);
+        *self.desired_power_mode.lock().unwrap() = Some(mode_val);
+        
+        for (fan_idx, fan) in profile.fans().iter().enumerate() {
+            let fan_id = fan_idx as u8 + 1;
+            let fan_mode = FanMode::from_str(&fan.mode)
+                .ok_or_else(|| format!("Invalid fan mode: {}", fan.mode))?;
+            
+            // Curves and limits first, curve mode picks its initial level from them
+            {
+                let mut curves = self.fan_curves.lock().unwrap();
+                curves[fan_idx].rampup_curve = fan.rampup_curve;
+                curves[fan_idx].rampdown_curve = fan.rampdown_curve;
+                if let Some(limits) = fan.limits {
+                    curves[fan_idx].limits = limits;
+                }
+            }
+            
+            self.set_fan_mode(fan_id, fan_mode)?;
+            
+            if fan_mode == FanMode::Fixed {
+                if let Some(clamp) = self.write_limited_level(fan_id, fan.level)? {
+                    log::warn!(event = "limit_clamp", fan_id = fan_id, old_level = clamp.requested_level, new_level = clamp.level;
+                        "Fan{} level {} of the profile clamped to {}: {}", fan_id, clamp.requested_level, clamp.level, clamp.reason);
+                    applied.fans_mut()[fan_idx].level = clamp.level;
+                }
+            }
+        }
+        Ok(applied)
+    }
+
+    fn read_fan_mode(&self, fan_id: u8) -> Result<&'static str, String> {
+        let mode_reg = self.get_fan_mode_register(fan_id)?;
+        let mode_val = self.read_byte(mode_reg)?;
+        
+        let curves = self.fan_curves.lock().unwrap();
+        let fan_idx = (fan_id - 1) as usize;
+        
+        match mode_val {
+            0x10 | 0x20 | 0x30 => Ok("auto"),
+            0x11 | 0x21 | 0x31 => {
+                // Check stored mode to distinguish between fixed, curve and target
+                match curves[fan_idx].mode {
+                    FanMode::Curve => Ok("curve"),
+                    FanMode::Target => Ok("target"),
+                    _ => Ok("fixed"),
+                }
+            },
+            _ => Err(format!("Unknown fan mode: 0x{:02X}", mode_val)),
+        }
+    }
+
+    fn set_fan_mode(&self, fan_id: u8, fan_mode: FanMode) -> Result<(), String> {
+        let mode_reg = self.get_fan_mode_register(fan_id)?;
+        let base_val = match fan_id {
+            1 => 0x10,
+            2 => 0x20,
+            3 => 0x30,
+            _ => return Err(format!("Invalid fan ID: {}", fan_id)),
+        };
+        
+        let mode_val = match fan_mode {
+            FanMode::Auto => base_val,
+            FanMode::Fixed | FanMode::Curve | FanMode::Target => base_val + 1,
+        };
+        
+        // Update stored mode
+        {
+            let mut curves = self.fan_curves.lock().unwrap();
+            let fan_idx = (fan_id - 1) as usize;
+            curves[fan_idx].mode = fan_mode;
+            curves[fan_idx].mode_applied = true;
+        }
+        
+        self.write_byte(mode_reg, mode_val)?;
+        
+        // When switching to curve mode, set initial fan level based on current temperature
+        if fan_mode == FanMode::Curve {
+            if let Ok(temp) = self.read_fan_temperature(fan_id) {
+                let curves = self.fan_curves.lock().unwrap();
+                let fan_idx = (fan_id - 1) as usize;
+                let (_, active) = self.active_curves(&curves[fan_idx]);
+                let initial_level = curve::initial_level(temp, &active.rampup_curve);
+                
+                drop(curves); // Release lock before calling write_limited_level
+                self.write_limited_level(fan_id, initial_level)?;
+            }
+        }
+        
+        // When switching to target mode, continue from the current fan level
+        if fan_mode == FanMode::Target {
+            let current_level = self.read_fan_level(fan_id)?;
+            let mut curves = self.fan_curves.lock().unwrap();
+            let fan_idx = (fan_id - 1) as usize;
+            let settings = curves[fan_idx].target;
+            curves[fan_idx].pid.reset(&settings, current_level);
         }
+        
+        Ok(())
     }
 
     fn get_fan_speed_registers(&self, fan_id: u8) -> Result<(u8, u8), String> {
@@ -403,7 +807,36 @@ impl EcController {
             _ => 0x7, // default to off
         };
         
-        self.write_byte(mode_reg + 1, level_val)
+        self.write_byte(mode_reg + 1, level_val)?;
+        self.fan_curves.lock().unwrap()[(fan_id - 1) as usize].last_level = Some(level);
+        Ok(())
+    }
+
+    fn fan_limits(&self, fan_id: u8) -> Result<FanLimits, String> {
+        if !(1..=3).contains(&fan_id) {
+            return Err(format!("Invalid fan ID: {}", fan_id));
+        }
+        
+        let curves = self.fan_curves.lock().unwrap();
+        Ok(curves[(fan_id - 1) as usize].limits)
+    }
+
+    // Temperature is only read if the limits depend on it, must not be called with the fan_curves lock held
+    fn clamp_level(&self, fan_id: u8, limits: &FanLimits, level: u8) -> Option<Clamp> {
+        let temperature = if limits.needs_temperature() {
+            self.read_fan_temperature(fan_id).ok()
+        } else {
+            None
+        };
+        limits.clamp(level, temperature)
+    }
+
+    // Writes the level moved into the fan's limits, for levels the server picks on its own
+    fn write_limited_level(&self, fan_id: u8, level: u8) -> Result<Option<Clamp>, String> {
+        let limits = self.fan_limits(fan_id)?;
+        let clamp = self.clamp_level(fan_id, &limits, level);
+        self.write_fan_level(fan_id, clamp.as_ref().map_or(level, |clamp| clamp.level))?;
+        Ok(clamp)
     }
 
     fn read_fan_level(&self, fan_id: u8) -> Result<u8, String> {
@@ -423,9 +856,22 @@ impl EcController {
         Ok(level)
     }
 
-    pub fn update_curve_fans(&self) -> Result<Vec<String>, String> {
-        let mut log_messages = Vec::new();
-        let temp = self.read_byte(EC_REG_APU_TEMPERATURE)?;
+    pub fn update_curve_fans(&self) -> Result<(), String> {
+        // Pick up power mode changes made outside of the server, e.g. by the BIOS or a hotkey
+        match self.read_byte(EC_REG_APU_POWER_MODE) {
+            Ok(mode_val) => {
+                let previous = self.power_mode.lock().unwrap().replace(mode_val);
+                if let Some(previous) = previous.filter(|previous| *previous != mode_val) {
+                    if let Some(mode) = POWER_MODES.get(mode_val as usize) {
+                        let old_mode = POWER_MODES.get(previous as usize).copied().unwrap_or("unknown");
+                        log::info!(event = "power_mode_change", old_mode = old_mode, new_mode = *mode;
+                            "Power mode is now {}, curve sets follow it", mode);
+                    }
+                }
+            }
+            // The fans are still driven, by the curve set of the last known power mode
+            Err(e) => log::warn!("Failed to read the power mode, keeping the curve sets of the last known one: {}", e),
+        }
         
         let curves = self.fan_curves.lock().unwrap();
         
@@ -433,45 +879,308 @@ impl EcController {
             let fan_idx = (fan_id - 1) as usize;
             
             if curves[fan_idx].mode == FanMode::Curve {
+                let temp = self.read_fan_temperature(fan_id)?;
                 let current_level = self.read_fan_level(fan_id)?;
-                let mut new_level = current_level;
+                let (_, active) = self.active_curves(&curves[fan_idx]);
                 
-                // Check if we should ramp up
-                if current_level < 5 && temp >= curves[fan_idx].rampup_curve[current_level as usize] {
-                    new_level = current_level + 1;
-                    log_messages.push(format!("Fan{} ramping up to level {} (temp: {}°C, threshold: {}°C)",
-                        fan_id, new_level, temp, curves[fan_idx].rampup_curve[current_level as usize]));
+                if let Some(step) = curve::next_level(current_level, temp, &active.rampup_curve, &active.rampdown_curve) {
+                    // Ramping stops at the fan's limits
+                    let level = curves[fan_idx].limits.clamp(step.level, Some(temp))
+                        .map_or(step.level, |clamp| clamp.level);
+                    if level == current_level {
+                        continue;
+                    }
+                    
+                    let direction = if level > current_level { "up" } else { "down" };
+                    log::info!(event = "curve_transition", fan_id = fan_id, temperature = temp, old_level = current_level, new_level = level;
+                        "Fan{} ramping {} to level {} (temp: {}°C, threshold: {}°C)", fan_id, direction, level, temp, step.threshold);
+                    
+                    drop(curves); // Release lock before writing
+                    self.write_fan_level(fan_id, level)?;
+                    return Ok(()); // Return early to reacquire lock on next iteration
                 }
-                // Check if we should ramp down
-                else if current_level > 0 && temp <= curves[fan_idx].rampdown_curve[(current_level - 1) as usize] {
-                    new_level = current_level - 1;
-                    log_messages.push(format!("Fan{} ramping down to level {} (temp: {}°C, threshold: {}°C)",
-                        fan_id, new_level, temp, curves[fan_idx].rampdown_curve[(current_level - 1) as usize]));
+            }
+        }
+        
+        Ok(())
+    }
+
+    pub fn update_target_fans(&self, dt: f32) -> Result<(), String> {
+        for fan_id in 1..=3 {
+            let fan_idx = (fan_id - 1) as usize;
+            
+            if self.fan_curves.lock().unwrap()[fan_idx].mode != FanMode::Target {
+                continue;
+            }
+            
+            let temp = self.read_fan_temperature(fan_id)?;
+            let current_level = self.read_fan_level(fan_id)?;
+            
+            let (new_level, output, settings) = {
+                let mut curves = self.fan_curves.lock().unwrap();
+                let settings = curves[fan_idx].target;
+                let output = curves[fan_idx].pid.update(&settings, temp, dt);
+                let new_level = pid::quantize(output, current_level, &settings);
+                let new_level = curves[fan_idx].limits.clamp(new_level, Some(temp))
+                    .map_or(new_level, |clamp| clamp.level);
+                (new_level, output, settings)
+            };
+            
+            if new_level != current_level {
+                log::info!(event = "target_transition", fan_id = fan_id, temperature = temp, old_level = current_level, new_level = new_level;
+                    "Fan{} target control to level {} (temp: {}°C, target: {}°C, output: {:.2})",
+                    fan_id, new_level, temp, settings.target_temperature, output);
+                self.write_fan_level(fan_id, new_level)?;
+            }
+        }
+        
+        Ok(())
+    }
+
+    // Moves fans back into their limits, e.g. after the temperature rose above a minimum level threshold
+    // or the mode changed without a new level
+    pub fn enforce_fan_limits(&self) -> Result<(), String> {
+        for fan_id in 1..=3 {
+            let fan_idx = (fan_id - 1) as usize;
+            let (mode, limits) = {
+                let curves = self.fan_curves.lock().unwrap();
+                (curves[fan_idx].mode, curves[fan_idx].limits)
+            };
+            
+            // The EC picks the level itself in auto mode
+            if mode == FanMode::Auto || limits.is_unlimited() {
+                continue;
+            }
+            
+            let current_level = self.read_fan_level(fan_id)?;
+            if let Some(clamp) = self.clamp_level(fan_id, &limits, current_level) {
+                log::warn!(event = "limit_clamp", fan_id = fan_id, old_level = current_level, new_level = clamp.level;
+                    "Fan{} moved from level {} to {} ({})", fan_id, current_level, clamp.level, clamp.reason);
+                self.write_fan_level(fan_id, clamp.level)?;
+            }
+        }
+        
+        Ok(())
+    }
+
+    // Compares the power mode and fan mode registers with what the server last wrote and handles
+    // differences according to their policy
+    fn reconcile(&self, settings: &ReconcileSettings) -> Result<Vec<DriftEvent>, String> {
+        let mut drifts = Vec::new();
+        let power_mode_name = |val: u8| POWER_MODES.get(val as usize)
+            .map(|mode| mode.to_string())
+            .unwrap_or_else(|| format!("0x{:02X}", val));
+        
+        let actual = self.read_byte(EC_REG_APU_POWER_MODE)?;
+        let desired = *self.desired_power_mode.lock().unwrap();
+        match desired {
+            // Nothing written yet, whatever the EC has is fine
+            None => *self.desired_power_mode.lock().unwrap() = Some(actual),
+            Some(desired) if desired != actual => {
+                let policy = settings.power_mode;
+                let error = match policy {
+                    DriftPolicy::Enforce => self.write_byte(EC_REG_APU_POWER_MODE, desired).err(),
+                    DriftPolicy::Adopt if (actual as usize) < POWER_MODES.len() => {
+                        *self.desired_power_mode.lock().unwrap() = Some(actual);
+                        None
+                    }
+                    DriftPolicy::Adopt => Some("Unknown power mode can't be adopted".to_string()),
+                    DriftPolicy::Alert => None,
+                };
+                if policy == DriftPolicy::Enforce && error.is_none() {
+                    *self.power_mode.lock().unwrap() = Some(desired);
                 }
                 
-                if new_level != current_level {
-                    drop(curves); // Release lock before writing
-                    self.write_fan_level(fan_id, new_level)?;
-                    return Ok(log_messages); // Return early to reacquire lock on next iteration
+                drifts.push(DriftEvent {
+                    time: Utc::now(),
+                    setting: "power_mode".to_string(),
+                    expected: power_mode_name(desired),
+                    actual: power_mode_name(actual),
+                    policy,
+                    error,
+                });
+            }
+            _ => {}
+        }
+        
+        for fan_id in 1..=3 {
+            let fan_idx = (fan_id - 1) as usize;
+            let (mode, mode_applied, last_level) = {
+                let curves = self.fan_curves.lock().unwrap();
+                (curves[fan_idx].mode, curves[fan_idx].mode_applied, curves[fan_idx].last_level)
+            };
+            
+            // Fans the server never touched are left in whatever state they are
+            if !mode_applied {
+                continue;
+            }
+            
+            let mode_reg = self.get_fan_mode_register(fan_id)?;
+            let base_val = fan_id * 0x10;
+            let expected = if mode == FanMode::Auto { base_val } else { base_val + 1 };
+            let actual = self.read_byte(mode_reg)?;
+            if actual == expected {
+                continue;
+            }
+            
+            let policy = settings.fan_mode(fan_id);
+            let error = match policy {
+                DriftPolicy::Enforce => {
+                    // Fixed mode also gets its level back, the EC may have reset it too
+                    self.set_fan_mode(fan_id, mode)
+                        .and_then(|_| match (mode, last_level) {
+                            (FanMode::Fixed, Some(level)) => self.write_fan_level(fan_id, level),
+                            _ => Ok(()),
+                        })
+                        .err()
+                }
+                DriftPolicy::Adopt if actual == base_val || actual == base_val + 1 => {
+                    // Manual mode can't tell fixed, curve and target apart, so it's taken over as fixed
+                    let adopted = if actual == base_val { FanMode::Auto } else { FanMode::Fixed };
+                    self.fan_curves.lock().unwrap()[fan_idx].mode = adopted;
+                    None
                 }
+                DriftPolicy::Adopt => Some("Unknown fan mode can't be adopted".to_string()),
+                DriftPolicy::Alert => None,
+            };
+            
+            let actual_name = match actual {
+                v if v == base_val => "auto".to_string(),
+                v if v == base_val + 1 => "manual".to_string(),
+                v => format!("0x{:02X}", v),
+            };
+            drifts.push(DriftEvent {
+                time: Utc::now(),
+                setting: format!("fan{}_mode", fan_id),
+                expected: mode.as_str().to_string(),
+                actual: actual_name,
+                policy,
+                error,
+            });
+        }
+        
+        Ok(drifts)
+    }
+    
+    // Writes the power mode and fan modes and levels the server last set again, returns what failed
+    pub fn replay_desired_state(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        
+        let desired = *self.desired_power_mode.lock().unwrap();
+        if let Some(power_mode) = desired {
+            match self.write_byte(EC_REG_APU_POWER_MODE, power_mode) {
+                Ok(()) => *self.power_mode.lock().unwrap() = Some(power_mode),
+                Err(e) => errors.push(format!("power mode: {}", e)),
             }
         }
         
-        Ok(log_messages)
+        for fan_id in 1..=3 {
+            let fan_idx = (fan_id - 1) as usize;
+            let (mode, mode_applied, last_level) = {
+                let curves = self.fan_curves.lock().unwrap();
+                (curves[fan_idx].mode, curves[fan_idx].mode_applied, curves[fan_idx].last_level)
+            };
+            if !mode_applied {
+                continue;
+            }
+            
+            let result = self.set_fan_mode(fan_id, mode)
+                .and_then(|_| match (mode, last_level) {
+                    (FanMode::Fixed, Some(level)) => self.write_fan_level(fan_id, level),
+                    _ => Ok(()),
+                });
+            if let Err(e) = result {
+                errors.push(format!("fan{} mode: {}", fan_id, e));
+            }
+        }
+        
+        errors
     }
 
     pub fn has_curve_fans(&self) -> bool {
         let curves = self.fan_curves.lock().unwrap();
-        curves.iter().any(|curve| curve.mode == FanMode::Curve)
+        curves.iter().any(|curve| curve.mode == FanMode::Curve || curve.mode == FanMode::Target)
+    }
+
+    fn read_apu_temperature(&self) -> Result<u8, String> {
+        let temp = self.read_byte(EC_REG_APU_TEMPERATURE)?;
+        
+        record_temperature(&mut self.temperature_history.lock().unwrap(), temp);
+        Ok(temp)
+    }
+
+    // Curves the engine uses right now: the set for the current power mode, or the default curves
+    fn active_curves(&self, data: &FanCurveData) -> (Option<&'static str>, FanCurveSet) {
+        let power_mode = *self.power_mode.lock().unwrap();
+        if let Some(mode_idx) = power_mode.map(|v| v as usize).filter(|&i| i < POWER_MODES.len()) {
+            if let Some(set) = data.power_mode_curves[mode_idx] {
+                return (Some(POWER_MODES[mode_idx]), set);
+            }
+        }
+        
+        (None, FanCurveSet {
+            rampup_curve: data.rampup_curve,
+            rampdown_curve: data.rampdown_curve,
+        })
+    }
+
+    // Temperature that drives curve and target mode for the given fan
+    fn read_fan_temperature(&self, fan_id: u8) -> Result<u8, String> {
+        let fan_idx = (fan_id - 1) as usize;
+        let source = {
+            let sources = self.temperature_sources.lock().unwrap();
+            sources[fan_idx].clone()
+        };
+        let temp = self.read_source_temperature(&source)?;
+        
+        // APU readings are recorded on their own
+        if source != TemperatureSource::Apu {
+            record_temperature(&mut self.fan_temperature_history.lock().unwrap()[fan_idx], temp);
+        }
+        Ok(temp)
+    }
+
+    fn read_source_temperature(&self, source: &TemperatureSource) -> Result<u8, String> {
+        match source {
+            TemperatureSource::Apu => self.read_apu_temperature(),
+            TemperatureSource::EcRegister { register } => self.read_byte(*register),
+            TemperatureSource::Pushed { name, fallback } => {
+                let sensor = self.pushed_sensors.lock().unwrap().get(name).copied();
+                match sensor {
+                    Some(sensor) if !sensor.is_stale(Utc::now()) => Ok(sensor.temperature),
+                    // Missing or stale values fall back to the configured safe source
+                    _ => self.read_source_temperature(fallback),
+                }
+            }
+            TemperatureSource::Max { sources } => {
+                let mut max = 0;
+                for source in sources {
+                    max = max.max(self.read_source_temperature(source)?);
+                }
+                Ok(max)
+            }
+            TemperatureSource::Avg { sources } => {
+                if sources.is_empty() {
+                    return Err("Aggregate temperature source has no sources".to_string());
+                }
+                let mut sum = 0u32;
+                for source in sources {
+                    sum += self.read_source_temperature(source)? as u32;
+                }
+                let count = sources.len() as u32;
+                Ok(((sum + count / 2) / count) as u8)
+            }
+        }
     }
 
     fn read_io_port(&self, port: u32) -> Result<u8, String> {
         let mut value: u32 = 0;
         let mut bytes_returned: u32 = 0;
 
+        let handle = self.driver_handle.lock().unwrap();
         let success = unsafe {
             DeviceIoControl(
-                self.driver_handle,
+                *handle,
                 IOCTL_OLS_READ_IO_PORT_BYTE,
                 &port as *const u32 as *mut _,
                 std::mem::size_of::<u32>() as u32,
@@ -484,8 +1193,10 @@ impl EcController {
 
         if success == 0 {
             let error = unsafe { GetLastError() };
+            self.io_failures.fetch_add(1, Ordering::Relaxed);
             Err(format!("Failed to read IO port 0x{:X}. Error code: {}", port, error))
         } else {
+            self.io_failures.store(0, Ordering::Relaxed);
             Ok((value & 0xFF) as u8)
         }
     }
@@ -497,9 +1208,10 @@ impl EcController {
         };
         let mut bytes_returned: u32 = 0;
 
+        let handle = self.driver_handle.lock().unwrap();
         let success = unsafe {
             DeviceIoControl(
-                self.driver_handle,
+                *handle,
                 IOCTL_OLS_WRITE_IO_PORT_BYTE,
                 &input as *const WriteIoPortInput as *mut _,
                 std::mem::size_of::<WriteIoPortInput>() as u32,
@@ -512,8 +1224,10 @@ impl EcController {
 
         if success == 0 {
             let error = unsafe { GetLastError() };
+            self.io_failures.fetch_add(1, Ordering::Relaxed);
             Err(format!("Failed to write IO port 0x{:X} value 0x{:02X}. Error code: {}", port, value, error))
         } else {
+            self.io_failures.store(0, Ordering::Relaxed);
             Ok(())
         }
     }
@@ -602,11 +1316,26 @@ impl EcController {
     }
 }
 
+// Records a sample for curve simulations, at most one per curve tick
+fn record_temperature(history: &mut TemperatureSamples, temp: u8) {
+    let now = Utc::now();
+    let is_new_tick = history.back()
+        .map(|(last, _)| (now - *last).num_seconds() >= curve::CURVE_TICK_SECONDS as i64)
+        .unwrap_or(true);
+    if is_new_tick {
+        if history.len() == TEMPERATURE_HISTORY_LEN {
+            history.pop_front();
+        }
+        history.push_back((now, temp));
+    }
+}
+
 impl Drop for EcController {
     fn drop(&mut self) {
-        if self.driver_handle != INVALID_HANDLE_VALUE {
+        let handle = *self.driver_handle.lock().unwrap();
+        if handle != INVALID_HANDLE_VALUE {
             unsafe {
-                CloseHandle(self.driver_handle);
+                CloseHandle(handle);
             }
         }
     }
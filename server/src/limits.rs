@@ -0,0 +1,113 @@
+// Per-fan safety limits that every fan level written by the server has to respect
+
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+// Minimum level that applies while the fan's temperature is at or above a threshold
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq)]
+pub struct TemperatureMinimum {
+    pub temperature: u8,
+    pub level: u8,
+}
+
+// What happens to requested levels outside the limits, levels chosen by the server are always clamped
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitViolation {
+    #[default]
+    Clamp,
+    Reject,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq)]
+pub struct FanLimits {
+    #[serde(default)]
+    pub min_level: u8,
+    #[serde(default = "default_max_level")]
+    pub max_level: u8,
+    #[serde(default)]
+    pub min_level_above: Option<TemperatureMinimum>,
+    #[serde(default)]
+    pub limit_violation: LimitViolation,
+}
+
+// Requested level that was moved into the limits
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Clamp {
+    pub requested_level: u8,
+    pub level: u8,
+    pub reason: String,
+}
+
+fn default_max_level() -> u8 {
+    5
+}
+
+impl Default for FanLimits {
+    fn default() -> Self {
+        FanLimits {
+            min_level: 0,
+            max_level: default_max_level(),
+            min_level_above: None,
+            limit_violation: LimitViolation::default(),
+        }
+    }
+}
+
+impl FanLimits {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_level > 5 || self.min_level > self.max_level {
+            return Err("Level limits must satisfy 0 <= min_level <= max_level <= 5".to_string());
+        }
+        if let Some(minimum) = self.min_level_above {
+            if minimum.temperature > 100 {
+                return Err("Temperature must be 0-100°C".to_string());
+            }
+            if minimum.level > self.max_level {
+                return Err("Minimum level above a temperature must not exceed max_level".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    // Fails for a level outside min_level and max_level if such levels are rejected. The temperature minimum
+    // depends on the temperature when the level is written, so it isn't checked.
+    pub fn check_rejected(&self, level: u8) -> Result<(), String> {
+        if self.limit_violation == LimitViolation::Reject && (level < self.min_level || level > self.max_level) {
+            return Err(format!("Fixed level {} is outside the level limits {}-{}, which reject it",
+                level, self.min_level, self.max_level));
+        }
+        Ok(())
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.min_level == 0 && self.max_level == 5 && self.min_level_above.is_none()
+    }
+
+    // Only the temperature minimum depends on the temperature
+    pub fn needs_temperature(&self) -> bool {
+        self.min_level_above.is_some()
+    }
+
+    // An unknown temperature is treated as hot, so a failing sensor never lets the fan stop
+    pub fn clamp(&self, level: u8, temperature: Option<u8>) -> Option<Clamp> {
+        let hot_minimum = self.min_level_above
+            .filter(|minimum| temperature.is_none_or(|temp| temp >= minimum.temperature))
+            .filter(|minimum| minimum.level > self.min_level);
+
+        let (clamped, reason) = match hot_minimum {
+            Some(minimum) if level < minimum.level => {
+                (minimum.level, format!("minimum level is {} at {}°C and above", minimum.level, minimum.temperature))
+            }
+            _ if level < self.min_level => (self.min_level, format!("minimum level is {}", self.min_level)),
+            _ if level > self.max_level => (self.max_level, format!("maximum level is {}", self.max_level)),
+            _ => return None,
+        };
+
+        Some(Clamp {
+            requested_level: level,
+            level: clamped,
+            reason,
+        })
+    }
+}
@@ -1,15 +1,224 @@
-use std::fs::{File, OpenOptions};
-use std::io::{Write, BufWriter};
-use std::path::Path;
-use chrono::Utc;
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write, BufWriter};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, RwLock};
+use std::thread::{self, JoinHandle};
+use chrono::{DateTime, SecondsFormat, Utc};
+use env_logger::filter::{Builder, Filter};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::kv::{self, Key, VisitSource};
+use log::{LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use schemars::JsonSchema;
+
+// Records of the log crate, from the server and its dependencies, are written by the Logger once init()
+// was called
+static SERVER_LOG: ServerLog = ServerLog {
+    logger: Mutex::new(None),
+    filter: RwLock::new(None),
+};
+
+// Minimum level of records that are logged, modules can have their own, e.g. "warp": "warn" or
+// "ec_su_axb35_server::restore": "debug"
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct LogLevels {
+    #[serde(default = "default_level")]
+    pub level: String,
+    #[serde(default)]
+    pub modules: BTreeMap<String, String>,
+}
+
+fn default_level() -> String {
+    "info".to_string()
+}
+
+impl Default for LogLevels {
+    fn default() -> Self {
+        LogLevels {
+            level: default_level(),
+            modules: BTreeMap::new(),
+        }
+    }
+}
+
+fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    LevelFilter::from_str(level)
+        .map_err(|_| format!("Invalid log level: {} (must be off, error, warn, info, debug or trace)", level))
+}
+
+impl LogLevels {
+    pub fn validate(&self) -> Result<(), String> {
+        self.filter().map(|_| ())
+    }
+
+    fn filter(&self) -> Result<Filter, String> {
+        let mut builder = Builder::new();
+        builder.filter_level(parse_level(&self.level)?);
+        for (module, level) in &self.modules {
+            if module.is_empty() {
+                return Err("Log module name must not be empty".to_string());
+            }
+            builder.filter_module(module, parse_level(level)?);
+        }
+        Ok(builder.build())
+    }
+}
+
+// Layout of the lines in the log file, the console always gets text
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    #[default]
+    Text,  // [timestamp] LEVEL: message
+    Json,  // One JSON object per line with timestamp, level, target, message and the fields of the event
+}
+
+// Key-value pairs of a record, e.g. event = "curve_transition", fan_id = 1
+struct Fields(Map<String, Value>);
+
+impl<'kvs> VisitSource<'kvs> for Fields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        let value = if let Some(number) = value.to_u64() {
+            Value::from(number)
+        } else if let Some(number) = value.to_i64() {
+            Value::from(number)
+        } else if let Some(number) = value.to_f64() {
+            Value::from(number)
+        } else if let Some(flag) = value.to_bool() {
+            Value::from(flag)
+        } else {
+            Value::from(value.to_string())
+        };
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+struct ServerLog {
+    logger: Mutex<Option<Logger>>,
+    filter: RwLock<Option<Filter>>,
+}
+
+impl Log for ServerLog {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.filter.read().unwrap().as_ref().is_some_and(|filter| filter.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.filter.read().unwrap().as_ref().is_some_and(|filter| filter.matches(record)) {
+            return;
+        }
+
+        let mut fields = Fields(Map::new());
+        let _ = record.key_values().visit(&mut fields);
+        if let Some(ref mut logger) = *self.logger.lock().unwrap() {
+            logger.log_record(record, fields.0);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(ref mut logger) = *self.logger.lock().unwrap() {
+            logger.flush();
+        }
+    }
+}
+
+// Opens the log file and makes it the target of the log crate
+pub fn init(log_path: &str, service_mode: bool, rotation: LogRotation, format: LogFormat, levels: &LogLevels) -> Result<(), String> {
+    let logger = Logger::new(log_path, service_mode, rotation, format)?;
+    set_levels(levels)?;
+    *SERVER_LOG.logger.lock().unwrap() = Some(logger);
+    log::set_logger(&SERVER_LOG)
+        .map_err(|e| format!("Failed to set up logging: {}", e))
+}
+
+// Takes effect right away, for PUT /logging/level and reloaded configs
+pub fn set_levels(levels: &LogLevels) -> Result<(), String> {
+    let filter = levels.filter()?;
+    log::set_max_level(filter.filter());
+    *SERVER_LOG.filter.write().unwrap() = Some(filter);
+    Ok(())
+}
+
+// Settings from a reloaded config, append only matters on start
+pub fn set_output(rotation: LogRotation, format: LogFormat) {
+    if let Some(ref mut logger) = *SERVER_LOG.logger.lock().unwrap() {
+        logger.rotation = rotation;
+        logger.format = format;
+    }
+}
+
+// Rotated files are the log path with .1 (newest) to .N appended, and .gz if compressed
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq)]
+pub struct LogRotation {
+    #[serde(default = "default_append")]
+    pub append: bool,         // Continue the existing log on start, otherwise it's rotated and a new one begins
+    #[serde(default = "default_max_size_mb")]
+    pub max_size_mb: u64,     // 0 disables rotation by size
+    #[serde(default = "default_max_age_hours")]
+    pub max_age_hours: u64,   // 0 disables rotation by age
+    #[serde(default = "default_keep_files")]
+    pub keep_files: u32,      // Rotated files kept, older ones are deleted
+    #[serde(default)]
+    pub compress: bool,       // Gzip rotated files
+}
+
+const MAX_KEEP_FILES: u32 = 100;
+
+fn default_append() -> bool {
+    true
+}
+
+fn default_max_size_mb() -> u64 {
+    10
+}
+
+fn default_max_age_hours() -> u64 {
+    24
+}
+
+fn default_keep_files() -> u32 {
+    7
+}
+
+impl Default for LogRotation {
+    fn default() -> Self {
+        LogRotation {
+            append: default_append(),
+            max_size_mb: default_max_size_mb(),
+            max_age_hours: default_max_age_hours(),
+            keep_files: default_keep_files(),
+            compress: false,
+        }
+    }
+}
+
+impl LogRotation {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.keep_files > MAX_KEEP_FILES {
+            return Err(format!("Log rotation keep_files must be 0-{}", MAX_KEEP_FILES));
+        }
+        Ok(())
+    }
+}
 
 pub struct Logger {
     file_writer: Option<BufWriter<File>>,
     service_mode: bool,
+    log_path: PathBuf,
+    rotation: LogRotation,
+    format: LogFormat,
+    size: u64,                 // Bytes in the current file
+    started: DateTime<Utc>,    // When the current file was begun
+    compressing: Option<JoinHandle<()>>,  // Compression of the last rotated file
 }
 
 impl Logger {
-    pub fn new(log_path: &str, service_mode: bool) -> Result<Self, String> {
+    fn new(log_path: &str, service_mode: bool, rotation: LogRotation, format: LogFormat) -> Result<Self, String> {
         // Create directory if it doesn't exist
         if let Some(parent) = Path::new(log_path).parent() {
             if !parent.exists() {
@@ -18,29 +227,153 @@ impl Logger {
             }
         }
 
-        // Open log file (overwrite existing)
+        let mut logger = Logger {
+            file_writer: None,
+            service_mode,
+            log_path: PathBuf::from(log_path),
+            rotation,
+            format,
+            size: 0,
+            started: Utc::now(),
+            compressing: None,
+        };
+
+        // The previous log is kept as a rotated file unless it's continued
+        let existing = fs::metadata(log_path).ok().filter(|metadata| metadata.len() > 0);
+        match existing {
+            Some(metadata) if rotation.append => {
+                logger.size = metadata.len();
+                logger.started = metadata.created().map(DateTime::from).unwrap_or_else(|_| Utc::now());
+            }
+            Some(_) => logger.rotate_files()
+                .map_err(|e| format!("Failed to rotate log file {}: {}", log_path, e))?,
+            None => {}
+        }
+
+        logger.open()
+            .map_err(|e| format!("Failed to open log file {}: {}", log_path, e))?;
+
+        Ok(logger)
+    }
+
+    fn open(&mut self) -> io::Result<()> {
         let file = OpenOptions::new()
             .create(true)
-            .write(true)
-            .truncate(true)
-            .open(log_path)
-            .map_err(|e| format!("Failed to open log file {}: {}", log_path, e))?;
+            .append(true)
+            .open(&self.log_path)?;
+        self.file_writer = Some(BufWriter::new(file));
+        Ok(())
+    }
 
-        let file_writer = BufWriter::new(file);
+    fn rotated_path(&self, n: u32, compressed: bool) -> PathBuf {
+        let mut path = self.log_path.clone().into_os_string();
+        path.push(format!(".{}", n));
+        if compressed {
+            path.push(".gz");
+        }
+        PathBuf::from(path)
+    }
 
-        Ok(Logger {
-            file_writer: Some(file_writer),
-            service_mode,
-        })
+    fn needs_rotation(&self) -> bool {
+        let too_big = self.rotation.max_size_mb > 0 && self.size >= self.rotation.max_size_mb * 1024 * 1024;
+        let too_old = self.rotation.max_age_hours > 0
+            && Utc::now() - self.started >= chrono::Duration::hours(self.rotation.max_age_hours as i64);
+        too_big || too_old
+    }
+
+    // Moves the current file to .1, shifting the older ones up and dropping what's beyond keep_files
+    fn rotate_files(&mut self) -> io::Result<()> {
+        if let Some(mut writer) = self.file_writer.take() {
+            writer.flush()?;
+        }
+
+        // The last rotated file has to be compressed before the files are shifted, it's long done usually
+        if let Some(compressing) = self.compressing.take() {
+            let _ = compressing.join();
+        }
+
+        let keep = self.rotation.keep_files;
+        for compressed in [false, true] {
+            // The oldest kept file makes room, along with any left from a higher keep_files
+            for n in keep.max(1)..=MAX_KEEP_FILES {
+                let path = self.rotated_path(n, compressed);
+                if path.exists() {
+                    fs::remove_file(&path)?;
+                }
+            }
+            for n in (1..keep).rev() {
+                let path = self.rotated_path(n, compressed);
+                if path.exists() {
+                    fs::rename(&path, self.rotated_path(n + 1, compressed))?;
+                }
+            }
+        }
+
+        if keep == 0 {
+            fs::remove_file(&self.log_path)?;
+        } else {
+            let rotated = self.rotated_path(1, false);
+            fs::rename(&self.log_path, &rotated)?;
+            // Compressed on its own thread, logging goes on meanwhile
+            if self.rotation.compress {
+                let target = self.rotated_path(1, true);
+                self.compressing = Some(thread::spawn(move || {
+                    if let Err(e) = compress_file(&rotated, &target) {
+                        eprintln!("Failed to compress rotated log file {}: {}", rotated.display(), e);
+                    }
+                }));
+            }
+        }
+
+        self.size = 0;
+        self.started = Utc::now();
+        Ok(())
+    }
+
+    fn rotate(&mut self) {
+        if let Err(e) = self.rotate_files() {
+            eprintln!("Failed to rotate log file: {}", e);
+            // Tried again once the next limit is reached, not on every line
+            self.size = 0;
+            self.started = Utc::now();
+        }
+        if let Err(e) = self.open() {
+            eprintln!("Failed to open log file {}: {}", self.log_path.display(), e);
+        }
     }
 
-    fn log_message(&mut self, level: &str, message: &str) {
-        let timestamp = Utc::now().format("%Y-%m-%d %H:%M:%S UTC");
-        let log_line = format!("[{}] {}: {}", timestamp, level, message);
+    fn log_record(&mut self, record: &Record, fields: Map<String, Value>) {
+        let now = Utc::now();
+        let level = record.level().as_str();
+
+        // Records of dependencies are told apart by their module
+        let message = if record.target().starts_with(env!("CARGO_CRATE_NAME")) {
+            record.args().to_string()
+        } else {
+            format!("{}: {}", record.target(), record.args())
+        };
+        let text_line = format!("[{}] {}: {}", now.format("%Y-%m-%d %H:%M:%S UTC"), level, message);
 
         // Write to stdout only if not in service mode
         if !self.service_mode {
-            println!("{}", log_line);
+            println!("{}", text_line);
+        }
+
+        let log_line = match self.format {
+            LogFormat::Text => text_line,
+            LogFormat::Json => {
+                // The fixed fields win over event fields of the same name
+                let mut line = fields;
+                line.insert("timestamp".to_string(), Value::from(now.to_rfc3339_opts(SecondsFormat::Millis, true)));
+                line.insert("level".to_string(), Value::from(level));
+                line.insert("target".to_string(), Value::from(record.target()));
+                line.insert("message".to_string(), Value::from(record.args().to_string()));
+                Value::Object(line).to_string()
+            }
+        };
+
+        if self.needs_rotation() {
+            self.rotate();
         }
 
         // Write to file
@@ -49,31 +382,31 @@ impl Logger {
                 eprintln!("Failed to write to log file: {}", e);
             } else if let Err(e) = writer.flush() {
                 eprintln!("Failed to flush log file: {}", e);
+            } else {
+                self.size += log_line.len() as u64 + 1;
             }
         }
     }
 
-    pub fn info(&mut self, message: &str) {
-        self.log_message("INFO", message);
-    }
-
-    pub fn warn(&mut self, message: &str) {
-        self.log_message("WARN", message);
-    }
-
-    pub fn error(&mut self, message: &str) {
-        self.log_message("ERROR", message);
+    fn flush(&mut self) {
+        if let Some(ref mut writer) = self.file_writer {
+            let _ = writer.flush();
+        }
     }
+}
 
-    pub fn debug(&mut self, message: &str) {
-        self.log_message("DEBUG", message);
-    }
+// Gzips a rotated file, the original is removed once the compressed copy is complete
+fn compress_file(source: &Path, target: &Path) -> io::Result<()> {
+    let mut input = File::open(source)?;
+    let mut encoder = GzEncoder::new(File::create(target)?, Compression::default());
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?.sync_all()?;
+    drop(input);
+    fs::remove_file(source)
 }
 
 impl Drop for Logger {
     fn drop(&mut self) {
-        if let Some(ref mut writer) = self.file_writer {
-            let _ = writer.flush();
-        }
+        self.flush();
     }
-}
\ No newline at end of file
+}
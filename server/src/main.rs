@@ -1,3 +1,5 @@
+// The combined warp filter chain is deeper than the default limit allows
+#![recursion_limit = "256"]
 
 use std::sync::{Arc, Mutex};
 use std::ptr;
@@ -27,16 +29,32 @@ use windows_service::{
 use tokio::sync::mpsc;
 use warp::Filter;
 use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
 use clap::Parser;
 
 mod ec;
 mod config;
+mod config_overrides;
 mod logger;
+mod migrations;
 mod driver;
+mod curve;
+mod limits;
+mod pid;
+mod presets;
+mod process_rules;
+mod availability;
+mod reconcile;
+mod reload;
+mod restore;
+mod schema;
+mod overrides;
+mod profiles;
+mod schedules;
+mod sensors;
 
 use ec::{EcController, EcOperation, EcResult};
 use config::ServerConfig;
-use logger::Logger;
 use driver::DriverManager;
 
 #[derive(Parser, Debug)]
@@ -46,6 +64,45 @@ struct Args {
     /// Run in service mode (suppress GUI dialogs and stdout output)
     #[arg(long)]
     service: bool,
+
+    /// Config file to use instead of the default one, can also be set with EC_SERVER_CONFIG
+    #[arg(long, value_name = "PATH")]
+    config: Option<std::path::PathBuf>,
+
+    /// Print the JSON Schema of the config file and exit
+    #[arg(long)]
+    print_config_schema: bool,
+
+    /// Address to listen on instead of host from the config file, can also be set with EC_SERVER_HOST
+    #[arg(long)]
+    host: Option<String>,
+
+    /// Port to listen on instead of port from the config file, can also be set with EC_SERVER_PORT
+    #[arg(long)]
+    port: Option<u16>,
+
+    /// Log file instead of log_path from the config file, can also be set with EC_SERVER_LOG_PATH
+    #[arg(long, value_name = "PATH")]
+    log_path: Option<String>,
+
+    /// Driver directory instead of driver_path from the config file, can also be set with EC_SERVER_DRIVER_PATH
+    #[arg(long, value_name = "PATH")]
+    driver_path: Option<String>,
+
+    /// Override any other top level config setting, e.g. --set sensor_expiry_seconds=30. Every setting can
+    /// also be set with EC_SERVER_<SETTING>, the command line wins.
+    #[arg(long = "set", value_name = "SETTING=VALUE", value_parser = parse_setting)]
+    set: Vec<(String, String)>,
+
+    /// Print the value of every config setting and where it comes from (cli, env, file or default) and exit
+    #[arg(long)]
+    print_effective_config: bool,
+}
+
+fn parse_setting(arg: &str) -> Result<(String, String), String> {
+    arg.split_once('=')
+        .map(|(setting, value)| (setting.trim().to_string(), value.to_string()))
+        .ok_or_else(|| format!("Expected SETTING=VALUE, got {}", arg))
 }
 
 const SERVICE_NAME: &str = "EC-SU-AXB35-Server";
@@ -63,16 +120,57 @@ static SERVICE_STATUS_HANDLE: OnceLock<StdMutex<Option<service_control_handler::
 struct StatusResponse {
     status: u8,
     version: Option<String>,
+    ec: availability::EcStatus,       // Whether the EC could be initialized, and why not
+    restore: restore::RestoreReport,  // Result of restoring the config on startup
+    config_corrupt: bool,             // The config file couldn't be read on startup and a backup was loaded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    config_fallback: Option<config::ConfigFallback>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    config_reload: Option<reload::ReloadStatus>,  // Result of the last reload of the config file
+}
+
+#[derive(Debug, Deserialize)]
+struct ConfigQuery {
+    #[serde(default)]
+    redact_paths: bool,  // Leave out log_path and driver_path, they only make sense on this machine
+    #[serde(default)]
+    dry_run: bool,       // Only validate and report the differences
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ConfigImportResponse {
+    dry_run: bool,
+    changes: Vec<reload::ConfigChange>,
+    ec_writes: Vec<String>,  // Settings written to the EC, or that would be written
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemasResponse {
+    schemas: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct EcUnavailableResponse {
+    error: String,
+    ec: availability::EcStatus,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct PowerModeResponse {
     power_mode: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    override_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    change_id: Option<u64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 struct PowerModeRequest {
     power_mode: String,
+    #[serde(default)]
+    duration_seconds: Option<u64>,  // Apply as a timed override instead of saving to config
+    #[serde(default)]
+    confirm_timeout_seconds: Option<u64>,  // Apply as a pending change, reverted unless confirmed in time
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -88,31 +186,206 @@ struct FanRpmResponse {
 #[derive(Debug, Serialize, Deserialize)]
 struct FanModeResponse {
     mode: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    override_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    change_id: Option<u64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 struct FanModeRequest {
     mode: String,
+    #[serde(default)]
+    duration_seconds: Option<u64>,  // Apply as a timed override instead of saving to config
+    #[serde(default)]
+    confirm_timeout_seconds: Option<u64>,  // Apply as a pending change, reverted unless confirmed in time
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct FanLevelResponse {
     level: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    override_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    change_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    clamped: Option<limits::Clamp>,  // Set if the requested level was moved into the fan's limits
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 struct FanLevelRequest {
     level: u8,
+    #[serde(default)]
+    duration_seconds: Option<u64>,  // Apply as a timed override instead of saving to config
+    #[serde(default)]
+    confirm_timeout_seconds: Option<u64>,  // Apply as a pending change, reverted unless confirmed in time
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct FanCurveResponse {
     curve: [u8; 5],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    override_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    change_id: Option<u64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 struct FanCurveRequest {
     curve: [u8; 5],
+    #[serde(default)]
+    duration_seconds: Option<u64>,  // Apply as a timed override instead of saving to config
+    #[serde(default)]
+    confirm_timeout_seconds: Option<u64>,  // Apply as a pending change, reverted unless confirmed in time
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct FanTargetRequest {
+    target_temperature: Option<u8>,
+    kp: Option<f32>,
+    ki: Option<f32>,
+    kd: Option<f32>,
+    min_level: Option<u8>,
+    max_level: Option<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct SensorPushRequest {
+    temperature: u8,
+    expires_in_seconds: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SensorResponse {
+    name: String,
+    temperature: u8,
+    updated: chrono::DateTime<chrono::Utc>,
+    expires: chrono::DateTime<chrono::Utc>,
+    stale: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SensorsResponse {
+    sensors: Vec<SensorResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct FanCurveSimulationRequest {
+    rampup_curve: Option<[u8; 5]>,
+    rampdown_curve: Option<[u8; 5]>,
+    temperatures: Option<Vec<u8>>,
+    interval_seconds: Option<u64>,
+    initial_level: Option<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FanCurveSimulationResponse {
+    rampup_curve: [u8; 5],
+    rampdown_curve: [u8; 5],
+    steps: Vec<FanCurveSimulationStep>,
+    transitions: u32,
+    time_at_level: [u64; 6],
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FanCurveSimulationStep {
+    temperature: u8,
+    duration_seconds: u64,
+    level: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    clamped: Option<limits::Clamp>,  // Set if the fan's limits moved the level
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CurvePresetResponse {
+    name: String,
+    builtin: bool,
+    rampup_curve: [u8; 5],
+    rampdown_curve: [u8; 5],
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CurvePresetsResponse {
+    presets: Vec<CurvePresetResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct CurvePresetApplyRequest {
+    name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ActiveProfileResponse {
+    name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchedulesResponse {
+    schedules: Vec<schedules::Schedule>,
+    active: Option<usize>,
+    next_boundary: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct ScheduleOverrideRequest {
+    profile: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OverrideResponse {
+    id: u64,
+    #[serde(flatten)]
+    setting: overrides::Setting,
+    pending: bool,
+    created: chrono::DateTime<chrono::Utc>,
+    expires: chrono::DateTime<chrono::Utc>,
+    remaining_seconds: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OverridesResponse {
+    overrides: Vec<OverrideResponse>,
+}
+
+#[derive(Debug, Serialize)]
+struct DriftResponse {
+    events: Vec<reconcile::DriftEvent>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ProcessRulesResponse {
+    rules: Vec<process_rules::ProcessRule>,
+    matched: Vec<bool>,
+    active: Option<usize>,
+}
+
+// What decides the applied profile instead of the settings saved in config
+#[derive(Debug, Clone, PartialEq)]
+enum AutomationTrigger {
+    Process(process_rules::ProcessRule),
+    Override(schedules::ScheduleOverride),
+    Schedule(schedules::Schedule),
+}
+
+impl AutomationTrigger {
+    fn profile(&self) -> &str {
+        match self {
+            AutomationTrigger::Process(rule) => &rule.profile,
+            AutomationTrigger::Override(schedule_override) => &schedule_override.profile,
+            AutomationTrigger::Schedule(schedule) => &schedule.profile,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            AutomationTrigger::Process(rule) => format!("profile {} for processes {}", rule.profile, rule.processes.join(", ")),
+            AutomationTrigger::Override(schedule_override) => match schedule_override.until {
+                Some(until) => format!("profile {} overriding the schedules until {}", schedule_override.profile, until),
+                None => format!("profile {} overriding the schedules", schedule_override.profile),
+            },
+            AutomationTrigger::Schedule(schedule) => format!("profile {} for schedule {}-{}", schedule.profile, schedule.start, schedule.end),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -131,6 +404,7 @@ struct FanMetrics {
     rpm: u16,
     rampup_curve: [u8; 5],
     rampdown_curve: [u8; 5],
+    active_curve_set: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -334,6 +608,41 @@ async fn main() {
     // Parse command line arguments
     let args = Args::parse();
     
+    // The service entry point has no access to the arguments, the path and the overrides are kept for it
+    config::set_config_path(args.config);
+    
+    let mut cli_overrides = args.set;
+    let flags = [
+        ("host", args.host),
+        ("port", args.port.map(|port| port.to_string())),
+        ("log_path", args.log_path),
+        ("driver_path", args.driver_path),
+    ];
+    for (setting, value) in flags {
+        if let Some(value) = value {
+            cli_overrides.push((setting.to_string(), value));
+        }
+    }
+    if let Err(e) = config_overrides::init(cli_overrides) {
+        show_error_and_exit(&e, args.service);
+    }
+    
+    if args.print_config_schema {
+        println!("{}", serde_json::to_string_pretty(&schema::config_schema()).unwrap_or_default());
+        return;
+    }
+    
+    if args.print_effective_config {
+        match ServerConfig::effective() {
+            Ok(settings) => println!("{}", serde_json::to_string_pretty(&settings).unwrap_or_default()),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    
     // Check if we're being started by the Service Control Manager
     if args.service || !has_console() {
         // We're running as a service
@@ -383,30 +692,38 @@ async fn run_server_with_shutdown(service_mode: bool, shutdown_rx: tokio::sync::
     }
 
     // Load configuration
-    let config = match ServerConfig::load() {
-        Ok(config) => Arc::new(Mutex::new(config)),
+    let (config, load_info) = match ServerConfig::load() {
+        Ok((config, load_info)) => (Arc::new(Mutex::new(config)), load_info),
         Err(e) => {
             show_error_and_exit(&format!("Failed to load configuration: {}", e), service_mode);
         }
     };
 
     // Initialize logger
-    let logger = {
+    {
         let config_guard = config.lock().unwrap();
-        match Logger::new(&config_guard.log_path, service_mode) {
-            Ok(logger) => Arc::new(Mutex::new(logger)),
-            Err(e) => {
-                show_error_and_exit(&format!("Failed to initialize logger: {}", e), service_mode);
-            }
+        if let Err(e) = logger::init(&config_guard.log_path, service_mode, config_guard.log_rotation, config_guard.log_format, &config_guard.log_levels) {
+            show_error_and_exit(&format!("Failed to initialize logger: {}", e), service_mode);
         }
-    };
+    }
 
     // Log startup
     {
-        let mut log = logger.lock().unwrap();
-        log.info("EC Server starting up...");
+        log::info!("EC Server starting up...");
         let config_guard = config.lock().unwrap();
-        log.info(&format!("Listening on {}:{}", config_guard.host, config_guard.port));
+        log::info!("Using config file {}", config::config_path().display());
+        for (setting, source) in config_overrides::active() {
+            log::info!("Config setting {} is overridden by {}", setting, source);
+        }
+        if let Some(ref fallback) = load_info.fallback {
+            log::error!("CONFIG FILE IS CORRUPT: {}", fallback.error);
+            log::error!("Loaded the backup {} instead, the corrupt file was copied to {}", fallback.backup, fallback.corrupt);
+        }
+        if let (Some(version), Some(backup)) = (load_info.migrated_from, &load_info.backup) {
+            log::info!("Config upgraded from version {} to {}, the original was saved as {}",
+                version, migrations::CURRENT_CONFIG_VERSION, backup.display());
+        }
+        log::info!("Listening on {}:{}", config_guard.host, config_guard.port);
     }
 
     // Initialize driver manager
@@ -415,126 +732,89 @@ async fn run_server_with_shutdown(service_mode: bool, shutdown_rx: tokio::sync::
         DriverManager::new(&config_guard.driver_path)
     };
     
-    // Check if driver is loaded or try to load it
-    if !driver_manager.is_driver_loaded() {
-        {
-            let mut log = logger.lock().unwrap();
-            log.info("WinRing0 driver not loaded, attempting to load...");
-        }
-        
-        if let Err(e) = driver_manager.install_and_load_driver() {
-            let error_msg = format!("Failed to load WinRing0 driver: {}. Make sure the driver files are in the correct location.", e);
-            {
-                let mut log = logger.lock().unwrap();
-                log.error(&error_msg);
-            }
-            show_error_and_exit(&error_msg, service_mode);
-        }
-        
-        {
-            let mut log = logger.lock().unwrap();
-            log.info("WinRing0 driver loaded successfully");
-        }
-    } else {
-        let mut log = logger.lock().unwrap();
-        log.info("WinRing0 driver already loaded");
-    }
+    let ec_state = Arc::new(availability::EcState::default());
+    let restore_report = Arc::new(Mutex::new(restore::RestoreReport::default()));
+    let (startup_tx, startup_rx) = tokio::sync::oneshot::channel();
 
-    // Initialize EC controller
-    let ec_controller = match EcController::new() {
-        Ok(controller) => Arc::new(controller),
-        Err(e) => {
-            let error_msg = format!("Failed to initialize EC controller: {}", e);
-            {
-                let mut log = logger.lock().unwrap();
-                log.error(&error_msg);
+    // Spawn EC initialization task. Until the driver and the EC can be opened the API is served in a
+    // degraded state, retrying with backoff.
+    let ec_state_init = ec_state.clone();
+    let restore_report_init = restore_report.clone();
+    let config_init = config.clone();
+    tokio::spawn(async move {
+        let mut attempts = 0;
+        let ec_controller = loop {
+            attempts += 1;
+            match open_ec(&driver_manager) {
+                Ok(controller) => break Arc::new(controller),
+                Err(e) => {
+                    let delay = availability::retry_delay_seconds(attempts);
+                    log::error!("{}, running degraded and retrying in {} seconds", e, delay);
+                    ec_state_init.set_unavailable(e, attempts, Some(chrono::Utc::now() + chrono::Duration::seconds(delay as i64)));
+                    tokio::time::sleep(tokio::time::Duration::from_secs(delay)).await;
+                }
             }
-            show_error_and_exit(&error_msg, service_mode);
-        }
-    };
+        };
 
-    {
-        let mut log = logger.lock().unwrap();
-        log.info("EC controller initialized successfully");
-    }
+        log::info!("EC controller initialized successfully");
 
-    // Restore saved parameters from config
-    {
-        let mut log = logger.lock().unwrap();
-        log.info("Restoring saved parameters from configuration...");
-    }
-    
-    let config_guard = config.lock().unwrap();
-    
-    // Restore APU power mode if saved
-    if let Some(ref power_mode) = config_guard.apu_power_mode {
-        if ec_controller.execute_operation(EcOperation::SetApuPowerMode(power_mode.clone())).await.is_ok() {
-            let mut log = logger.lock().unwrap();
-            log.info(&format!("Restored APU power mode: {}", power_mode));
-        }
-    }
-    
-    // Restore fan configurations
-    let fan_configs = [&config_guard.fan1, &config_guard.fan2, &config_guard.fan3];
-    for (fan_id, fan_config_opt) in fan_configs.iter().enumerate() {
-        let fan_id = (fan_id + 1) as u8;
+        let (report, startup_schedule, schedule_fallback) = restore_saved_state(&ec_controller, &config_init).await;
+        *restore_report_init.lock().unwrap() = report;
+        ec_state_init.set_available(ec_controller.clone());
+        let _ = startup_tx.send((startup_schedule, schedule_fallback));
         
-        if let Some(fan_config) = fan_config_opt {
-            // Restore fan mode
-            if ec_controller.execute_operation(EcOperation::SetFanMode(fan_id, fan_config.mode.clone())).await.is_ok() {
-                let mut log = logger.lock().unwrap();
-                log.info(&format!("Restored Fan{} mode: {}", fan_id, fan_config.mode));
+        // Watch for persistent port I/O failures and reconnect, degraded again until it works
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(1));
+        loop {
+            interval.tick().await;
+            if !ec_controller.io_failing() {
+                continue;
             }
             
-            // Restore fan level if not in auto mode
-            if fan_config.mode != "auto" {
-                if ec_controller.execute_operation(EcOperation::SetFanLevel(fan_id, fan_config.level)).await.is_ok() {
-                    let mut log = logger.lock().unwrap();
-                    log.info(&format!("Restored Fan{} level: {}", fan_id, fan_config.level));
-                }
-            }
+            log::error!("EC port I/O keeps failing, reopening the driver");
+            ec_state_init.set_unavailable("EC port I/O keeps failing, reconnecting".to_string(), 0, None);
             
-            // Restore fan curves
-            if ec_controller.execute_operation(EcOperation::SetFanRampupCurve(fan_id, fan_config.rampup_curve)).await.is_ok() {
-                let mut log = logger.lock().unwrap();
-                log.info(&format!("Restored Fan{} rampup curve: {:?}", fan_id, fan_config.rampup_curve));
+            let mut attempts = 0;
+            loop {
+                attempts += 1;
+                match reconnect_ec(&ec_controller, &driver_manager) {
+                    Ok(()) => break,
+                    Err(e) => {
+                        let delay = availability::retry_delay_seconds(attempts);
+                        log::error!("{}, retrying in {} seconds", e, delay);
+                        ec_state_init.set_unavailable(e, attempts, Some(chrono::Utc::now() + chrono::Duration::seconds(delay as i64)));
+                        tokio::time::sleep(tokio::time::Duration::from_secs(delay)).await;
+                    }
+                }
             }
             
-            if ec_controller.execute_operation(EcOperation::SetFanRampdownCurve(fan_id, fan_config.rampdown_curve)).await.is_ok() {
-                let mut log = logger.lock().unwrap();
-                log.info(&format!("Restored Fan{} rampdown curve: {:?}", fan_id, fan_config.rampdown_curve));
+            // Whatever the server set before may be gone along with the driver
+            let errors = ec_controller.replay_desired_state();
+            for e in &errors {
+                log::warn!("Failed to replay {}", e);
             }
-        } else {
-            let mut log = logger.lock().unwrap();
-            log.info(&format!("Fan{} configuration not found in config, leaving in original state", fan_id));
+            log::info!("EC reconnected after {} attempts, desired state replayed", attempts);
+            ec_state_init.set_reconnected();
         }
-    }
-    
-    drop(config_guard);
-    
-    {
-        let mut log = logger.lock().unwrap();
-        log.info("Parameter restoration completed");
-    }
+    });
 
     // Create EC operation queue
     let (tx, mut rx) = mpsc::unbounded_channel::<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>();
     let ec_queue = Arc::new(tx);
 
     // Spawn EC operation handler task
-    let ec_controller_clone = ec_controller.clone();
-    let logger_clone = logger.clone();
+    let ec_state_queue = ec_state.clone();
     tokio::spawn(async move {
         while let Some((operation, response_tx)) = rx.recv().await {
-            let result = ec_controller_clone.execute_operation(operation).await;
+            let result = match ec_state_queue.controller() {
+                Some(ec_controller) => ec_controller.execute_operation(operation).await,
+                None => Err(ec_state_queue.unavailable_error()),
+            };
             
             // Log the operation
-            {
-                let mut log = logger_clone.lock().unwrap();
-                match &result {
-                    Ok(_) => log.debug("EC operation completed successfully"),
-                    Err(e) => log.warn(&format!("EC operation failed: {}", e)),
-                }
+            match &result {
+                Ok(_) => log::debug!("EC operation completed successfully"),
+                Err(e) => log::warn!("EC operation failed: {}", e),
             }
             
             let _ = response_tx.send(result);
@@ -542,8 +822,7 @@ async fn run_server_with_shutdown(service_mode: bool, shutdown_rx: tokio::sync::
     });
 
     // Spawn curve monitoring task
-    let ec_controller_curve = ec_controller.clone();
-    let logger_curve = logger.clone();
+    let ec_state_curve = ec_state.clone();
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(1));
         let mut curve_monitoring_active = false;
@@ -551,79 +830,351 @@ async fn run_server_with_shutdown(service_mode: bool, shutdown_rx: tokio::sync::
         loop {
             interval.tick().await;
             
+            let ec_controller_curve = match ec_state_curve.controller() {
+                Some(ec_controller) => ec_controller,
+                None => continue,
+            };
+            
             let has_curve_fans = ec_controller_curve.has_curve_fans();
             
             // Log when curve monitoring starts or stops
             if has_curve_fans && !curve_monitoring_active {
-                let mut log = logger_curve.lock().unwrap();
-                log.info("Curve monitoring started - fans in curve mode detected");
+                log::info!("Curve monitoring started - fans in curve or target mode detected");
                 curve_monitoring_active = true;
             } else if !has_curve_fans && curve_monitoring_active {
-                let mut log = logger_curve.lock().unwrap();
-                log.info("Curve monitoring stopped - no fans in curve mode");
+                log::info!("Curve monitoring stopped - no fans in curve or target mode");
                 curve_monitoring_active = false;
             }
             
             // Only run curve logic if any fans are in curve mode
             if has_curve_fans {
-                match ec_controller_curve.update_curve_fans() {
-                    Ok(log_messages) => {
-                        if !log_messages.is_empty() {
-                            let mut log = logger_curve.lock().unwrap();
-                            for message in log_messages {
-                                log.info(&message);
-                            }
-                        }
+                if let Err(e) = ec_controller_curve.update_curve_fans() {
+                    log::warn!("Curve monitoring error: {}", e);
+                }
+                
+                if let Err(e) = ec_controller_curve.update_target_fans(curve::CURVE_TICK_SECONDS as f32) {
+                    log::warn!("Target monitoring error: {}", e);
+                }
+            }
+            
+            // Level limits also apply to fixed fans, e.g. a minimum level above some temperature
+            if let Err(e) = ec_controller_curve.enforce_fan_limits() {
+                log::warn!("Level limit monitoring error: {}", e);
+            }
+        }
+    });
+
+    // Spawn automation task for process rules and schedules. It only acts when the rule or window in charge
+    // changes, so manual changes last until then. Process rules take precedence over schedule overrides,
+    // which take precedence over schedules.
+    let process_watcher = Arc::new(Mutex::new(process_rules::ProcessWatcher::default()));
+    let process_watcher_automation = process_watcher.clone();
+    let schedule_override = Arc::new(Mutex::new(None::<schedules::ScheduleOverride>));
+    let schedule_override_automation = schedule_override.clone();
+    let ec_queue_automation = ec_queue.clone();
+    let config_automation = config.clone();
+    tokio::spawn(async move {
+        // Nothing to automate until the EC is initialized and the saved state is restored
+        let (startup_schedule, schedule_fallback) = match startup_rx.await {
+            Ok(startup) => startup,
+            Err(_) => return,
+        };
+        
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(1));
+        let mut applied = startup_schedule.map(AutomationTrigger::Schedule);
+        let mut fallback = schedule_fallback;
+        let mut process_error_logged = false;
+        
+        // Helper function to execute EC operation
+        let execute_operation = |operation: EcOperation| {
+            let ec_queue = ec_queue_automation.clone();
+            async move {
+                let (tx, rx) = tokio::sync::oneshot::channel();
+                if ec_queue.send((operation, tx)).is_err() {
+                    return Err("EC queue unavailable".to_string());
+                }
+                match rx.await {
+                    Ok(result) => result,
+                    Err(_) => Err("Communication timeout".to_string()),
+                }
+            }
+        };
+        
+        loop {
+            interval.tick().await;
+            
+            let (rules, debounce) = {
+                let config_guard = config_automation.lock().unwrap();
+                let debounce = config_guard.process_debounce_seconds
+                    .unwrap_or(process_rules::DEFAULT_PROCESS_DEBOUNCE_SECONDS);
+                (config_guard.process_rules.clone(), std::time::Duration::from_secs(debounce))
+            };
+            
+            // Only look at the process list if there is a rule to match it against. Taking the snapshot blocks,
+            // so it runs off the async workers.
+            let running = if rules.is_empty() {
+                Default::default()
+            } else {
+                let running = tokio::task::spawn_blocking(process_rules::running_processes).await
+                    .unwrap_or_else(|e| Err(format!("Process snapshot task failed: {}", e)));
+                match running {
+                    Ok(running) => {
+                        process_error_logged = false;
+                        running
                     }
                     Err(e) => {
-                        let mut log = logger_curve.lock().unwrap();
-                        log.warn(&format!("Curve monitoring error: {}", e));
+                        if !process_error_logged {
+                            log::warn!("Process rules error: {}", e);
+                            process_error_logged = true;
+                        }
+                        Default::default()
+                    }
+                }
+            };
+            
+            let process_trigger = {
+                let mut watcher = process_watcher_automation.lock().unwrap();
+                watcher.update(&rules, &running, debounce, std::time::Instant::now());
+                watcher.active().map(|(_, rule)| AutomationTrigger::Process(rule.clone()))
+            };
+            
+            let now = chrono::Local::now().naive_local();
+            let override_trigger = {
+                let mut schedule_override = schedule_override_automation.lock().unwrap();
+                if schedule_override.as_ref().is_some_and(|schedule_override| schedule_override.has_ended(now)) {
+                    log::info!("Schedule override ended at the schedule boundary");
+                    *schedule_override = None;
+                }
+                schedule_override.clone().map(AutomationTrigger::Override)
+            };
+            
+            let (active, profile) = {
+                let config_guard = config_automation.lock().unwrap();
+                let active = process_trigger.or(override_trigger).or_else(|| {
+                    schedules::active(&config_guard.schedules, now)
+                        .map(|(_, schedule)| AutomationTrigger::Schedule(schedule.clone()))
+                });
+                let profile = match active {
+                    Some(ref trigger) => config_guard.profiles.get(trigger.profile()).cloned(),
+                    None => fallback.as_ref().map(|fallback| config_guard.saved_profile(fallback)),
+                };
+                (active, profile)
+            };
+            
+            if active == applied {
+                continue;
+            }
+            
+            // Taking over from the saved settings, remember the current state to return to
+            if applied.is_none() {
+                match execute_operation(EcOperation::GetProfileState).await {
+                    Ok(EcResult::Profile(state)) => fallback = Some(state),
+                    _ => {
+                        log::warn!("Failed to read the current state before applying a profile");
                     }
                 }
             }
+            
+            let description = match active {
+                Some(ref trigger) => trigger.describe(),
+                None => "saved settings, no process rule or schedule applies".to_string(),
+            };
+            applied = active;
+            
+            let profile = match profile {
+                Some(profile) => profile,
+                None => {
+                    log::warn!("Can't apply {}: profile not found", description);
+                    continue;
+                }
+            };
+            
+            match execute_operation(EcOperation::ApplyProfile(profile)).await {
+                Ok(_) => {
+                    log::info!("Applied {}", description);
+                }
+                Err(e) => {
+                    log::warn!("Failed to apply {}: {}", description, e);
+                }
+            }
+        }
+    });
+
+    // Spawn override task, reverting expired overrides to the persisted state
+    let overrides = Arc::new(Mutex::new(overrides::Overrides::default()));
+    let overrides_task = overrides.clone();
+    let ec_queue_overrides = ec_queue.clone();
+    let config_overrides = config.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(1));
+        
+        loop {
+            interval.tick().await;
+            
+            let expired = overrides_task.lock().unwrap().take_expired(chrono::Utc::now());
+            for entry in expired {
+                if let Err(e) = revert_override(&entry, &config_overrides, &ec_queue_overrides).await {
+                    log::warn!("Failed to revert expired override {}: {}", entry.id, e);
+                }
+            }
+        }
+    });
+
+    // Spawn reconciliation task, checking the EC registers for changes made behind the server's back
+    let drift_log = Arc::new(Mutex::new(reconcile::DriftLog::default()));
+    let drift_log_task = drift_log.clone();
+    let ec_queue_reconcile = ec_queue.clone();
+    let ec_state_reconcile = ec_state.clone();
+    let config_reconcile = config.clone();
+    tokio::spawn(async move {
+        loop {
+            let settings = config_reconcile.lock().unwrap().reconcile;
+            
+            // Disabled for now, the setting is checked again later
+            if settings.interval_seconds == 0 {
+                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                continue;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_secs(settings.interval_seconds)).await;
+            
+            if !ec_state_reconcile.is_available() {
+                continue;
+            }
+            
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            if ec_queue_reconcile.send((EcOperation::Reconcile(settings), tx)).is_err() {
+                continue;
+            }
+            
+            let drifts = match rx.await {
+                Ok(Ok(EcResult::Drift(drifts))) => drifts,
+                Ok(Err(e)) => {
+                    log::warn!("Reconciliation error: {}", e);
+                    continue;
+                }
+                _ => continue,
+            };
+            
+            let recorded = drift_log_task.lock().unwrap().record(drifts);
+            for drift in recorded {
+                let outcome = match (drift.policy, &drift.error) {
+                    (_, Some(e)) => format!("failed to handle: {}", e),
+                    (reconcile::DriftPolicy::Enforce, None) => "enforced".to_string(),
+                    (reconcile::DriftPolicy::Adopt, None) => "adopted".to_string(),
+                    (reconcile::DriftPolicy::Alert, None) => "left as is".to_string(),
+                };
+                log::warn!(event = "drift", setting = drift.setting.as_str(); "Drift detected for {}: expected {}, EC has {} ({})",
+                    drift.setting, drift.expected, drift.actual, outcome);
+            }
+        }
+    });
+
+    // Spawn config watcher task, applying changes made to the config file by hand
+    let reload_status = Arc::new(Mutex::new(None::<reload::ReloadStatus>));
+    let (rebind_tx, rebind_rx) = tokio::sync::watch::channel(());
+    let rebind_tx = Arc::new(rebind_tx);
+    let rebind_tx_filter = rebind_tx.clone();
+    let reload_status_watcher = reload_status.clone();
+    let ec_queue_watcher = ec_queue.clone();
+    let config_watcher = config.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(1));
+        let mut last_modified = config::modified();
+        
+        loop {
+            interval.tick().await;
+            
+            let modified = config::modified();
+            if modified == last_modified {
+                continue;
+            }
+            last_modified = modified;
+            
+            let status = match ServerConfig::reload() {
+                // Written by the server itself
+                Ok(None) => continue,
+                Ok(Some(new_config)) => apply_config(new_config, &config_watcher, &ec_queue_watcher, &rebind_tx, "Config file reloaded", false).await,
+                Err(e) => {
+                    log::error!(event = "config_rejected", error = e.as_str(); "Config file changed but can't be used, keeping the running config: {}", e);
+                    reload::ReloadStatus {
+                        time: chrono::Utc::now(),
+                        ok: false,
+                        error: Some(e),
+                        changes: Vec::new(),
+                        failed: Vec::new(),
+                    }
+                }
+            };
+            *reload_status_watcher.lock().unwrap() = Some(status);
         }
     });
 
     // Create routes
-    let logger_clone_for_filter = logger.clone();
-    let logger_filter = warp::any().map(move || logger_clone_for_filter.clone());
-    let ec_queue_filter = warp::any().map(move || ec_queue.clone());
+    let ec_state_filter = warp::any().map(move || ec_state.clone());
+    // For requests that have to work even while the EC can't be used
+    let ec_queue_unguarded = ec_queue.clone();
+    let ec_queue_unguarded_filter = warp::any().map(move || ec_queue_unguarded.clone());
+    let rebind_filter = warp::any().map(move || rebind_tx_filter.clone());
+    // EC endpoints are rejected with 503 while the EC can't be used
+    let ec_queue_filter = ec_state_filter.clone()
+        .and_then(move |ec_state: Arc<availability::EcState>| {
+            let ec_queue = ec_queue.clone();
+            async move {
+                if ec_state.is_available() {
+                    Ok(ec_queue)
+                } else {
+                    Err(warp::reject::custom(availability::EcUnavailable {
+                        error: ec_state.unavailable_error(),
+                        ec: ec_state.status(),
+                    }))
+                }
+            }
+        });
     let config_clone_for_filter = config.clone();
     let config_filter = warp::any().map(move || config_clone_for_filter.clone());
+    let process_watcher_filter = warp::any().map(move || process_watcher.clone());
+    let schedule_override_filter = warp::any().map(move || schedule_override.clone());
+    let overrides_filter = warp::any().map(move || overrides.clone());
+    let drift_log_filter = warp::any().map(move || drift_log.clone());
+    let restore_report_filter = warp::any().map(move || restore_report.clone());
+    let config_fallback = load_info.fallback.clone();
+    let config_fallback_filter = warp::any().map(move || config_fallback.clone());
+    let reload_status_listener = reload_status.clone();
+    let reload_status_filter = warp::any().map(move || reload_status.clone());
 
     // GET /status
     let status_route = warp::path("status")
         .and(warp::get())
-        .and(logger_filter.clone())
-        .and(ec_queue_filter.clone())
+        .and(ec_queue_unguarded_filter.clone())
+        .and(ec_state_filter.clone())
+        .and(restore_report_filter.clone())
+        .and(config_fallback_filter.clone())
+        .and(reload_status_filter.clone())
         .and_then(handle_status);
 
     // GET /metrics
     let metrics_route = warp::path("metrics")
         .and(warp::get())
-        .and(logger_filter.clone())
         .and(ec_queue_filter.clone())
         .and_then(handle_metrics);
 
     // GET/POST /apu/power_mode
     let apu_power_mode_get = warp::path!("apu" / "power_mode")
         .and(warp::get())
-        .and(logger_filter.clone())
         .and(ec_queue_filter.clone())
         .and_then(handle_apu_power_mode_get);
 
     let apu_power_mode_post = warp::path!("apu" / "power_mode")
         .and(warp::post())
         .and(warp::body::json())
-        .and(logger_filter.clone())
         .and(ec_queue_filter.clone())
         .and(config_filter.clone())
+        .and(overrides_filter.clone())
         .and_then(handle_apu_power_mode_post);
 
     // GET /apu/temp
     let apu_temp_route = warp::path!("apu" / "temp")
         .and(warp::get())
-        .and(logger_filter.clone())
         .and(ec_queue_filter.clone())
         .and_then(handle_apu_temp);
 
@@ -631,38 +1182,32 @@ async fn run_server_with_shutdown(service_mode: bool, shutdown_rx: tokio::sync::
     let fan_rpm_routes = warp::path!("fan1" / "rpm")
         .and(warp::get())
         .and(warp::any().map(|| 1u8))
-        .and(logger_filter.clone())
         .and(ec_queue_filter.clone())
         .and_then(handle_fan_rpm)
         .or(warp::path!("fan2" / "rpm")
             .and(warp::get())
             .and(warp::any().map(|| 2u8))
-            .and(logger_filter.clone())
             .and(ec_queue_filter.clone())
             .and_then(handle_fan_rpm))
         .or(warp::path!("fan3" / "rpm")
             .and(warp::get())
             .and(warp::any().map(|| 3u8))
-            .and(logger_filter.clone())
             .and(ec_queue_filter.clone())
             .and_then(handle_fan_rpm));
 
     let fan_mode_get_routes = warp::path!("fan1" / "mode")
         .and(warp::get())
         .and(warp::any().map(|| 1u8))
-        .and(logger_filter.clone())
         .and(ec_queue_filter.clone())
         .and_then(handle_fan_mode_get)
         .or(warp::path!("fan2" / "mode")
             .and(warp::get())
             .and(warp::any().map(|| 2u8))
-            .and(logger_filter.clone())
             .and(ec_queue_filter.clone())
             .and_then(handle_fan_mode_get))
         .or(warp::path!("fan3" / "mode")
             .and(warp::get())
             .and(warp::any().map(|| 3u8))
-            .and(logger_filter.clone())
             .and(ec_queue_filter.clone())
             .and_then(handle_fan_mode_get));
 
@@ -670,43 +1215,40 @@ async fn run_server_with_shutdown(service_mode: bool, shutdown_rx: tokio::sync::
         .and(warp::post())
         .and(warp::body::json())
         .and(warp::any().map(|| 1u8))
-        .and(logger_filter.clone())
         .and(ec_queue_filter.clone())
         .and(config_filter.clone())
+        .and(overrides_filter.clone())
         .and_then(handle_fan_mode_post)
         .or(warp::path!("fan2" / "mode")
             .and(warp::post())
             .and(warp::body::json())
             .and(warp::any().map(|| 2u8))
-            .and(logger_filter.clone())
             .and(ec_queue_filter.clone())
             .and(config_filter.clone())
+            .and(overrides_filter.clone())
             .and_then(handle_fan_mode_post))
         .or(warp::path!("fan3" / "mode")
             .and(warp::post())
             .and(warp::body::json())
             .and(warp::any().map(|| 3u8))
-            .and(logger_filter.clone())
             .and(ec_queue_filter.clone())
             .and(config_filter.clone())
+            .and(overrides_filter.clone())
             .and_then(handle_fan_mode_post));
 
     let fan_level_get_routes = warp::path!("fan1" / "level")
         .and(warp::get())
         .and(warp::any().map(|| 1u8))
-        .and(logger_filter.clone())
         .and(ec_queue_filter.clone())
         .and_then(handle_fan_level_get)
         .or(warp::path!("fan2" / "level")
             .and(warp::get())
             .and(warp::any().map(|| 2u8))
-            .and(logger_filter.clone())
             .and(ec_queue_filter.clone())
             .and_then(handle_fan_level_get))
         .or(warp::path!("fan3" / "level")
             .and(warp::get())
             .and(warp::any().map(|| 3u8))
-            .and(logger_filter.clone())
             .and(ec_queue_filter.clone())
             .and_then(handle_fan_level_get));
 
@@ -714,44 +1256,41 @@ async fn run_server_with_shutdown(service_mode: bool, shutdown_rx: tokio::sync::
         .and(warp::post())
         .and(warp::body::json())
         .and(warp::any().map(|| 1u8))
-        .and(logger_filter.clone())
         .and(ec_queue_filter.clone())
         .and(config_filter.clone())
+        .and(overrides_filter.clone())
         .and_then(handle_fan_level_post)
         .or(warp::path!("fan2" / "level")
             .and(warp::post())
             .and(warp::body::json())
             .and(warp::any().map(|| 2u8))
-            .and(logger_filter.clone())
             .and(ec_queue_filter.clone())
             .and(config_filter.clone())
+            .and(overrides_filter.clone())
             .and_then(handle_fan_level_post))
         .or(warp::path!("fan3" / "level")
             .and(warp::post())
             .and(warp::body::json())
             .and(warp::any().map(|| 3u8))
-            .and(logger_filter.clone())
             .and(ec_queue_filter.clone())
             .and(config_filter.clone())
+            .and(overrides_filter.clone())
             .and_then(handle_fan_level_post));
 
     // Fan curve routes
     let fan_rampup_curve_get_routes = warp::path!("fan1" / "rampup_curve")
         .and(warp::get())
         .and(warp::any().map(|| 1u8))
-        .and(logger_filter.clone())
         .and(ec_queue_filter.clone())
         .and_then(handle_fan_rampup_curve_get)
         .or(warp::path!("fan2" / "rampup_curve")
             .and(warp::get())
             .and(warp::any().map(|| 2u8))
-            .and(logger_filter.clone())
             .and(ec_queue_filter.clone())
             .and_then(handle_fan_rampup_curve_get))
         .or(warp::path!("fan3" / "rampup_curve")
             .and(warp::get())
             .and(warp::any().map(|| 3u8))
-            .and(logger_filter.clone())
             .and(ec_queue_filter.clone())
             .and_then(handle_fan_rampup_curve_get));
 
@@ -759,43 +1298,40 @@ async fn run_server_with_shutdown(service_mode: bool, shutdown_rx: tokio::sync::
         .and(warp::post())
         .and(warp::body::json())
         .and(warp::any().map(|| 1u8))
-        .and(logger_filter.clone())
         .and(ec_queue_filter.clone())
         .and(config_filter.clone())
+        .and(overrides_filter.clone())
         .and_then(handle_fan_rampup_curve_post)
         .or(warp::path!("fan2" / "rampup_curve")
             .and(warp::post())
             .and(warp::body::json())
             .and(warp::any().map(|| 2u8))
-            .and(logger_filter.clone())
             .and(ec_queue_filter.clone())
             .and(config_filter.clone())
+            .and(overrides_filter.clone())
             .and_then(handle_fan_rampup_curve_post))
         .or(warp::path!("fan3" / "rampup_curve")
             .and(warp::post())
             .and(warp::body::json())
             .and(warp::any().map(|| 3u8))
-            .and(logger_filter.clone())
             .and(ec_queue_filter.clone())
             .and(config_filter.clone())
+            .and(overrides_filter.clone())
             .and_then(handle_fan_rampup_curve_post));
 
     let fan_rampdown_curve_get_routes = warp::path!("fan1" / "rampdown_curve")
         .and(warp::get())
         .and(warp::any().map(|| 1u8))
-        .and(logger_filter.clone())
         .and(ec_queue_filter.clone())
         .and_then(handle_fan_rampdown_curve_get)
         .or(warp::path!("fan2" / "rampdown_curve")
             .and(warp::get())
             .and(warp::any().map(|| 2u8))
-            .and(logger_filter.clone())
             .and(ec_queue_filter.clone())
             .and_then(handle_fan_rampdown_curve_get))
         .or(warp::path!("fan3" / "rampdown_curve")
             .and(warp::get())
             .and(warp::any().map(|| 3u8))
-            .and(logger_filter.clone())
             .and(ec_queue_filter.clone())
             .and_then(handle_fan_rampdown_curve_get));
 
@@ -803,29 +1339,375 @@ async fn run_server_with_shutdown(service_mode: bool, shutdown_rx: tokio::sync::
         .and(warp::post())
         .and(warp::body::json())
         .and(warp::any().map(|| 1u8))
-        .and(logger_filter.clone())
         .and(ec_queue_filter.clone())
         .and(config_filter.clone())
+        .and(overrides_filter.clone())
         .and_then(handle_fan_rampdown_curve_post)
         .or(warp::path!("fan2" / "rampdown_curve")
             .and(warp::post())
             .and(warp::body::json())
             .and(warp::any().map(|| 2u8))
-            .and(logger_filter.clone())
             .and(ec_queue_filter.clone())
             .and(config_filter.clone())
+            .and(overrides_filter.clone())
             .and_then(handle_fan_rampdown_curve_post))
         .or(warp::path!("fan3" / "rampdown_curve")
             .and(warp::post())
             .and(warp::body::json())
             .and(warp::any().map(|| 3u8))
-            .and(logger_filter.clone())
             .and(ec_queue_filter.clone())
             .and(config_filter.clone())
+            .and(overrides_filter.clone())
             .and_then(handle_fan_rampdown_curve_post));
 
-    // Combine all routes
-    let routes = status_route
+    // Fan target temperature routes
+    let fan_target_get_routes = warp::path!("fan1" / "target")
+        .and(warp::get())
+        .and(warp::any().map(|| 1u8))
+        .and(ec_queue_filter.clone())
+        .and_then(handle_fan_target_get)
+        .or(warp::path!("fan2" / "target")
+            .and(warp::get())
+            .and(warp::any().map(|| 2u8))
+            .and(ec_queue_filter.clone())
+            .and_then(handle_fan_target_get))
+        .or(warp::path!("fan3" / "target")
+            .and(warp::get())
+            .and(warp::any().map(|| 3u8))
+            .and(ec_queue_filter.clone())
+            .and_then(handle_fan_target_get));
+
+    let fan_target_post_routes = warp::path!("fan1" / "target")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(warp::any().map(|| 1u8))
+        .and(ec_queue_filter.clone())
+        .and(config_filter.clone())
+        .and_then(handle_fan_target_post)
+        .or(warp::path!("fan2" / "target")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(warp::any().map(|| 2u8))
+            .and(ec_queue_filter.clone())
+            .and(config_filter.clone())
+            .and_then(handle_fan_target_post))
+        .or(warp::path!("fan3" / "target")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(warp::any().map(|| 3u8))
+            .and(ec_queue_filter.clone())
+            .and(config_filter.clone())
+            .and_then(handle_fan_target_post));
+
+    // Fan level limit routes
+    let fan_limits_get_routes = warp::path!("fan1" / "limits")
+        .and(warp::get())
+        .and(warp::any().map(|| 1u8))
+        .and(ec_queue_filter.clone())
+        .and_then(handle_fan_limits_get)
+        .or(warp::path!("fan2" / "limits")
+            .and(warp::get())
+            .and(warp::any().map(|| 2u8))
+            .and(ec_queue_filter.clone())
+            .and_then(handle_fan_limits_get))
+        .or(warp::path!("fan3" / "limits")
+            .and(warp::get())
+            .and(warp::any().map(|| 3u8))
+            .and(ec_queue_filter.clone())
+            .and_then(handle_fan_limits_get));
+
+    let fan_limits_post_routes = warp::path!("fan1" / "limits")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(warp::any().map(|| 1u8))
+        .and(ec_queue_filter.clone())
+        .and(config_filter.clone())
+        .and_then(handle_fan_limits_post)
+        .or(warp::path!("fan2" / "limits")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(warp::any().map(|| 2u8))
+            .and(ec_queue_filter.clone())
+            .and(config_filter.clone())
+            .and_then(handle_fan_limits_post))
+        .or(warp::path!("fan3" / "limits")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(warp::any().map(|| 3u8))
+            .and(ec_queue_filter.clone())
+            .and(config_filter.clone())
+            .and_then(handle_fan_limits_post));
+
+    // Fan power mode curve set routes
+    let fan_power_mode_curves_get_routes = warp::path!("fan1" / "power_mode_curves")
+        .and(warp::get())
+        .and(warp::any().map(|| 1u8))
+        .and(ec_queue_filter.clone())
+        .and_then(handle_fan_power_mode_curves_get)
+        .or(warp::path!("fan2" / "power_mode_curves")
+            .and(warp::get())
+            .and(warp::any().map(|| 2u8))
+            .and(ec_queue_filter.clone())
+            .and_then(handle_fan_power_mode_curves_get))
+        .or(warp::path!("fan3" / "power_mode_curves")
+            .and(warp::get())
+            .and(warp::any().map(|| 3u8))
+            .and(ec_queue_filter.clone())
+            .and_then(handle_fan_power_mode_curves_get));
+
+    let fan_power_mode_curves_post_routes = warp::path!("fan1" / "power_mode_curves")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(warp::any().map(|| 1u8))
+        .and(ec_queue_filter.clone())
+        .and(config_filter.clone())
+        .and_then(handle_fan_power_mode_curves_post)
+        .or(warp::path!("fan2" / "power_mode_curves")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(warp::any().map(|| 2u8))
+            .and(ec_queue_filter.clone())
+            .and(config_filter.clone())
+            .and_then(handle_fan_power_mode_curves_post))
+        .or(warp::path!("fan3" / "power_mode_curves")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(warp::any().map(|| 3u8))
+            .and(ec_queue_filter.clone())
+            .and(config_filter.clone())
+            .and_then(handle_fan_power_mode_curves_post));
+
+    // Fan temperature source routes
+    let fan_temperature_source_get_routes = warp::path!("fan1" / "temperature_source")
+        .and(warp::get())
+        .and(warp::any().map(|| 1u8))
+        .and(ec_queue_filter.clone())
+        .and_then(handle_fan_temperature_source_get)
+        .or(warp::path!("fan2" / "temperature_source")
+            .and(warp::get())
+            .and(warp::any().map(|| 2u8))
+            .and(ec_queue_filter.clone())
+            .and_then(handle_fan_temperature_source_get))
+        .or(warp::path!("fan3" / "temperature_source")
+            .and(warp::get())
+            .and(warp::any().map(|| 3u8))
+            .and(ec_queue_filter.clone())
+            .and_then(handle_fan_temperature_source_get));
+
+    let fan_temperature_source_post_routes = warp::path!("fan1" / "temperature_source")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(warp::any().map(|| 1u8))
+        .and(ec_queue_filter.clone())
+        .and(config_filter.clone())
+        .and_then(handle_fan_temperature_source_post)
+        .or(warp::path!("fan2" / "temperature_source")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(warp::any().map(|| 2u8))
+            .and(ec_queue_filter.clone())
+            .and(config_filter.clone())
+            .and_then(handle_fan_temperature_source_post))
+        .or(warp::path!("fan3" / "temperature_source")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(warp::any().map(|| 3u8))
+            .and(ec_queue_filter.clone())
+            .and(config_filter.clone())
+            .and_then(handle_fan_temperature_source_post));
+
+    // GET /sensors
+    let sensors_get_route = warp::path!("sensors")
+        .and(warp::get())
+        .and(ec_queue_filter.clone())
+        .and_then(handle_sensors_get);
+
+    // POST /sensors/{name}
+    let sensor_post_route = warp::path!("sensors" / String)
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(ec_queue_filter.clone())
+        .and(config_filter.clone())
+        .and_then(handle_sensor_post);
+
+    // POST /fans/{id}/curve/simulate
+    let fan_curve_simulate_route = warp::path!("fans" / u8 / "curve" / "simulate")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(ec_queue_filter.clone())
+        .and_then(handle_fan_curve_simulate);
+
+    // GET /presets/curves
+    let curve_presets_get_route = warp::path!("presets" / "curves")
+        .and(warp::get())
+        .and(config_filter.clone())
+        .and_then(handle_curve_presets_get);
+
+    // POST /fans/{id}/curve/preset
+    let fan_curve_preset_route = warp::path!("fans" / u8 / "curve" / "preset")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(ec_queue_filter.clone())
+        .and(config_filter.clone())
+        .and_then(handle_fan_curve_preset_post);
+
+    // GET /profiles
+    let profiles_get_route = warp::path!("profiles")
+        .and(warp::get())
+        .and(config_filter.clone())
+        .and_then(handle_profiles_get);
+
+    // GET /profiles/active, must come before GET /profiles/{name}
+    let profile_active_get_route = warp::path!("profiles" / "active")
+        .and(warp::get())
+        .and(ec_queue_filter.clone())
+        .and(config_filter.clone())
+        .and_then(handle_profile_active_get);
+
+    // GET /profiles/{name}
+    let profile_get_route = warp::path!("profiles" / String)
+        .and(warp::get())
+        .and(config_filter.clone())
+        .and_then(handle_profile_get);
+
+    // POST /profiles/{name}
+    let profile_post_route = warp::path!("profiles" / String)
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(config_filter.clone())
+        .and_then(handle_profile_post);
+
+    // DELETE /profiles/{name}
+    let profile_delete_route = warp::path!("profiles" / String)
+        .and(warp::delete())
+        .and(config_filter.clone())
+        .and(schedule_override_filter.clone())
+        .and_then(handle_profile_delete);
+
+    // POST /profiles/{name}/apply
+    let profile_apply_route = warp::path!("profiles" / String / "apply")
+        .and(warp::post())
+        .and(ec_queue_filter.clone())
+        .and(config_filter.clone())
+        .and_then(handle_profile_apply);
+
+    // GET /schedules
+    let schedules_get_route = warp::path!("schedules")
+        .and(warp::get())
+        .and(config_filter.clone())
+        .and_then(handle_schedules_get);
+
+    // POST /schedules
+    let schedules_post_route = warp::path!("schedules")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(config_filter.clone())
+        .and_then(handle_schedules_post);
+
+    // GET /schedules/override
+    let schedule_override_get_route = warp::path!("schedules" / "override")
+        .and(warp::get())
+        .and(schedule_override_filter.clone())
+        .and_then(handle_schedule_override_get);
+
+    // POST /schedules/override
+    let schedule_override_post_route = warp::path!("schedules" / "override")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(config_filter.clone())
+        .and(schedule_override_filter.clone())
+        .and_then(handle_schedule_override_post);
+
+    // DELETE /schedules/override
+    let schedule_override_delete_route = warp::path!("schedules" / "override")
+        .and(warp::delete())
+        .and(schedule_override_filter.clone())
+        .and_then(handle_schedule_override_delete);
+
+    // GET /process_rules
+    let process_rules_get_route = warp::path!("process_rules")
+        .and(warp::get())
+        .and(config_filter.clone())
+        .and(process_watcher_filter.clone())
+        .and_then(handle_process_rules_get);
+
+    // POST /process_rules
+    let process_rules_post_route = warp::path!("process_rules")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(config_filter.clone())
+        .and(process_watcher_filter.clone())
+        .and_then(handle_process_rules_post);
+
+    // GET /overrides
+    let overrides_get_route = warp::path!("overrides")
+        .and(warp::get())
+        .and(overrides_filter.clone())
+        .and_then(handle_overrides_get);
+
+    // DELETE /overrides/{id}
+    let override_delete_route = warp::path!("overrides" / u64)
+        .and(warp::delete())
+        .and(ec_queue_filter.clone())
+        .and(config_filter.clone())
+        .and(overrides_filter.clone())
+        .and_then(handle_override_delete);
+
+    // GET /drift
+    let drift_route = warp::path!("drift")
+        .and(warp::get())
+        .and(drift_log_filter.clone())
+        .and_then(handle_drift_get);
+
+    // POST /changes/{id}/confirm
+    let change_confirm_route = warp::path!("changes" / u64 / "confirm")
+        .and(warp::post())
+        .and(config_filter.clone())
+        .and(overrides_filter.clone())
+        .and_then(handle_change_confirm);
+
+    // GET /config
+    let config_get_route = warp::path!("config")
+        .and(warp::get())
+        .and(warp::query::<ConfigQuery>())
+        .and(config_filter.clone())
+        .and_then(handle_config_get);
+
+    // PUT /config
+    let config_put_route = warp::path!("config")
+        .and(warp::put())
+        .and(warp::query::<ConfigQuery>())
+        .and(warp::body::json())
+        .and(config_filter.clone())
+        .and(ec_queue_unguarded_filter.clone())
+        .and(rebind_filter.clone())
+        .and_then(handle_config_put);
+
+    // GET /logging/level
+    let logging_level_get_route = warp::path!("logging" / "level")
+        .and(warp::get())
+        .and(config_filter.clone())
+        .and_then(handle_logging_level_get);
+
+    // PUT /logging/level
+    let logging_level_put_route = warp::path!("logging" / "level")
+        .and(warp::put())
+        .and(warp::body::json())
+        .and(config_filter.clone())
+        .and_then(handle_logging_level_put);
+
+    // GET /schema
+    let schemas_route = warp::path!("schema")
+        .and(warp::get())
+        .and_then(handle_schemas_get);
+
+    // GET /schema/{name}
+    let schema_route = warp::path!("schema" / String)
+        .and(warp::get())
+        .and_then(handle_schema_get);
+
+    // Combine all routes
+    let routes = status_route
         .or(metrics_route)
         .or(apu_power_mode_get)
         .or(apu_power_mode_post)
@@ -839,64 +1721,1328 @@ async fn run_server_with_shutdown(service_mode: bool, shutdown_rx: tokio::sync::
         .or(fan_rampup_curve_post_routes)
         .or(fan_rampdown_curve_get_routes)
         .or(fan_rampdown_curve_post_routes)
-        .with(warp::cors().allow_any_origin().allow_headers(vec!["content-type"]).allow_methods(vec!["GET", "POST"]));
+        .or(fan_target_get_routes)
+        .or(fan_target_post_routes)
+        .or(fan_limits_get_routes)
+        .or(fan_limits_post_routes)
+        .or(fan_power_mode_curves_get_routes)
+        .or(fan_power_mode_curves_post_routes)
+        .or(fan_temperature_source_get_routes)
+        .or(fan_temperature_source_post_routes)
+        .or(sensors_get_route)
+        .or(sensor_post_route)
+        .or(fan_curve_simulate_route)
+        .or(curve_presets_get_route)
+        .or(fan_curve_preset_route)
+        .or(profiles_get_route)
+        .or(profile_active_get_route)
+        .or(profile_get_route)
+        .or(profile_post_route)
+        .or(profile_delete_route)
+        .or(profile_apply_route)
+        .or(schedules_get_route)
+        .or(schedules_post_route)
+        .or(schedule_override_get_route)
+        .or(schedule_override_post_route)
+        .or(schedule_override_delete_route)
+        .or(process_rules_get_route)
+        .or(process_rules_post_route)
+        .or(overrides_get_route)
+        .or(override_delete_route)
+        .or(change_confirm_route)
+        .or(drift_route)
+        .or(config_get_route)
+        .or(config_put_route)
+        .or(logging_level_get_route)
+        .or(logging_level_put_route)
+        .or(schemas_route)
+        .or(schema_route)
+        .recover(handle_rejection)
+        .with(warp::cors().allow_any_origin().allow_headers(vec!["content-type"]).allow_methods(vec!["GET", "POST", "PUT", "DELETE"]))
+        .with(warp::log::custom(log_request));
 
-    {
-        let mut log = logger.lock().unwrap();
-        log.info("Server started successfully");
-    }
+    log::info!("Server started successfully");
 
-    // Parse host address
-    let (host_addr, port) = {
-        let config_guard = config.lock().unwrap();
-        let host_addr: std::net::IpAddr = config_guard.host.parse()
-            .unwrap_or_else(|_| {
-                let error_msg = format!("Invalid host address in config: {}", config_guard.host);
-                {
-                    let mut log = logger.lock().unwrap();
-                    log.error(&error_msg);
+    // Every listener stops on shutdown, a rebind only stops the current one
+    let (shutdown_tx, shutdown_watch) = tokio::sync::watch::channel(false);
+    tokio::spawn(async move {
+        shutdown_rx.await.ok();
+        let _ = shutdown_tx.send(true);
+    });
+
+    let mut bound: Option<(std::net::IpAddr, u16)> = None;
+    loop {
+        // Parse host address
+        let (host_addr, port) = {
+            let config_guard = config.lock().unwrap();
+            let host_addr: std::net::IpAddr = config_guard.host.parse()
+                .unwrap_or_else(|_| {
+                    let error_msg = format!("Invalid host address in config: {}", config_guard.host);
+                    log::error!("{}", error_msg);
+                    show_error_and_exit(&error_msg, service_mode);
+                });
+            (host_addr, config_guard.port)
+        };
+        let (host_addr, port) = match bind_listener(routes.clone(), (host_addr, port), &shutdown_watch, &rebind_rx) {
+            Ok(server) => {
+                if bound.is_some() {
+                    log::info!("Listening on {}:{}", host_addr, port);
+                }
+                server.await;
+                (host_addr, port)
+            }
+            Err(e) => {
+                let error_msg = format!("Failed to bind to {}:{} - {}", host_addr, port, e);
+                log::error!("{}", error_msg);
+                
+                // Keep serving on the old address if the new one from a reload can't be used
+                let previous = match bound {
+                    Some(previous) => previous,
+                    None => {
+                        eprintln!("Error: {}", error_msg);
+                        std::process::exit(1);
+                    }
+                };
+                if let Some(ref mut status) = *reload_status_listener.lock().unwrap() {
+                    status.ok = false;
+                    status.failed.push(format!("listener: {}", error_msg));
+                }
+                match bind_listener(routes.clone(), previous, &shutdown_watch, &rebind_rx) {
+                    Ok(server) => {
+                        log::warn!("Still listening on {}:{}", previous.0, previous.1);
+                        server.await;
+                        previous
+                    }
+                    Err(e) => {
+                        let error_msg = format!("Failed to bind to {}:{} again - {}", previous.0, previous.1, e);
+                        log::error!("{}", error_msg);
+                        eprintln!("Error: {}", error_msg);
+                        std::process::exit(1);
+                    }
                 }
-                show_error_and_exit(&error_msg, service_mode);
-            });
-        (host_addr, config_guard.port)
+            }
+        };
+        bound = Some((host_addr, port));
+        
+        if *shutdown_watch.borrow() {
+            break;
+        }
+    }
+    
+    // Log shutdown
+    log::info!("Server shutdown completed");
+}
+
+// Starts serving on the address until shutdown or until the config asks for another address
+fn bind_listener<F>(
+    routes: F,
+    address: (std::net::IpAddr, u16),
+    shutdown: &tokio::sync::watch::Receiver<bool>,
+    rebind: &tokio::sync::watch::Receiver<()>,
+) -> Result<impl std::future::Future<Output = ()>, warp::Error>
+where
+    F: Filter + Clone + Send + Sync + 'static,
+    F::Extract: warp::Reply,
+{
+    let mut shutdown = shutdown.clone();
+    let mut rebind = rebind.clone();
+    rebind.mark_unchanged();
+    
+    let (_addr, server) = warp::serve(routes).try_bind_with_graceful_shutdown(address, async move {
+        tokio::select! {
+            _ = shutdown.wait_for(|stop| *stop) => {}
+            _ = rebind.changed() => {}
+        }
+    })?;
+    Ok(server)
+}
+
+// One event per request. Successful requests are only logged at debug level, the client polls several
+// endpoints every second.
+fn log_request(info: warp::log::Info) {
+    let status = info.status().as_u16();
+    let latency_ms = info.elapsed().as_secs_f64() * 1000.0;
+    let level = match status {
+        500.. => log::Level::Warn,
+        400.. => log::Level::Info,
+        _ => log::Level::Debug,
     };
+    let method = info.method().as_str();
+    let path = info.path();
 
-    // Start server with graceful shutdown
-    let server_result = warp::serve(routes)
-        .try_bind_with_graceful_shutdown((host_addr, port), async move {
-            shutdown_rx.await.ok();
-        });
+    log::log!(level, event = "http_request", method = method, path = path, status = status, latency_ms = latency_ms;
+        "{} {} {} ({:.1} ms)", method, path, status, latency_ms);
+}
+
+// Saved value of a setting for the old_* field of a log event
+fn saved_value<T: std::fmt::Debug>(value: Option<T>) -> String {
+    value.map(|value| format!("{:?}", value)).unwrap_or_else(|| "unknown".to_string())
+}
+
+// Takes over a validated config: changed power and fan settings are written to the EC first, then the config
+// is saved if asked to and replaces the running config, and the listener moves if the address changed.
+// If a write or the save fails, the settings already written are set back to the running config's values
+// and nothing else changes.
+async fn apply_config(
+    mut new_config: ServerConfig,
+    config: &Arc<Mutex<ServerConfig>>,
+    ec_queue: &Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
+    rebind: &tokio::sync::watch::Sender<()>,
+    origin: &str,
+    save: bool,
+) -> reload::ReloadStatus {
+    let old_config = config.lock().unwrap().clone();
+    let changes = reload::changed_settings(&old_config, &new_config);
     
-    let (_addr, server) = match server_result {
-        Ok(server) => server,
-        Err(e) => {
-            let error_msg = format!("Failed to bind to {}:{} - {}", host_addr, port, e);
-            {
-                let mut log = logger.lock().unwrap();
-                log.error(&error_msg);
-            }
-            eprintln!("Error: {}", error_msg);
-            std::process::exit(1);
+    let execute_operation = |operation: EcOperation| async {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        if ec_queue.send((operation, tx)).is_err() {
+            return Err("EC queue unavailable".to_string());
+        }
+        match rx.await {
+            Ok(result) => result.map(|_| ()),
+            Err(_) => Err("Communication timeout".to_string()),
         }
     };
     
-    server.await;
+    let mut failed = Vec::new();
+    for (setting, operation) in reload::ec_operations(&old_config, &new_config) {
+        if let Err(e) = execute_operation(operation).await {
+            failed.push(format!("{}: {}", setting, e));
+            break;
+        }
+    }
+    let mut error = failed.first().map(|failure| format!("Failed to apply {}", failure));
+    if error.is_none() && save {
+        error = new_config.save().err().map(|e| format!("Failed to save config: {}", e));
+    }
     
-    // Log shutdown
-    {
-        let mut log = logger.lock().unwrap();
-        log.info("Server shutdown completed");
+    if let Some(error) = error {
+        log::warn!(event = "config_apply_failed", origin = origin, error = error.as_str(); "{}, rolling back: {}", origin, error);
+        // Writing the old values again also covers the settings written before the failure
+        for (setting, operation) in reload::ec_operations(&new_config, &old_config) {
+            if let Err(e) = execute_operation(operation).await {
+                log::error!(event = "config_rollback_failed", setting = setting.as_str(), error = e.as_str(); "Failed to roll back {}: {}", setting, e);
+            }
+        }
+        return reload::ReloadStatus {
+            time: chrono::Utc::now(),
+            ok: false,
+            error: Some(error),
+            changes: Vec::new(),
+            failed,
+        };
+    }
+    
+    *config.lock().unwrap() = new_config.clone();
+    
+    if new_config.host != old_config.host || new_config.port != old_config.port {
+        let _ = rebind.send(());
+    }
+    
+    logger::set_output(new_config.log_rotation, new_config.log_format);
+    if let Err(e) = logger::set_levels(&new_config.log_levels) {
+        log::warn!("Failed to apply log_levels: {}", e);
+    }
+    
+    if changes.is_empty() {
+        log::info!("{}, nothing changed", origin);
+    } else {
+        let settings = changes.join(", ");
+        log::info!(event = "config_change", origin = origin, settings = settings.as_str(); "{}, changed: {}", origin, settings);
+    }
+    if new_config.log_path != old_config.log_path || new_config.driver_path != old_config.driver_path {
+        log::warn!("log_path and driver_path changes take effect after a restart");
+    }
+    
+    reload::ReloadStatus {
+        time: chrono::Utc::now(),
+        ok: true,
+        error: None,
+        changes,
+        failed,
+    }
+}
+
+// Loads the driver if needed and opens the EC
+fn open_ec(driver_manager: &DriverManager) -> Result<EcController, String> {
+    if !driver_manager.is_driver_loaded() {
+        log::info!("WinRing0 driver not loaded, attempting to load...");
+        
+        driver_manager.install_and_load_driver()
+            .map_err(|e| format!("Failed to load WinRing0 driver: {}. Make sure the driver files are in the correct location.", e))?;
+        
+        log::info!("WinRing0 driver loaded successfully");
+    } else {
+        log::info!("WinRing0 driver already loaded");
+    }
+
+    EcController::new().map_err(|e| format!("Failed to initialize EC controller: {}", e))
+}
+
+// Loads the driver again if it's gone and reopens the device
+fn reconnect_ec(ec_controller: &EcController, driver_manager: &DriverManager) -> Result<(), String> {
+    if !driver_manager.is_driver_loaded() {
+        log::info!("WinRing0 driver not loaded anymore, attempting to load...");
+        
+        driver_manager.install_and_load_driver()
+            .map_err(|e| format!("Failed to load WinRing0 driver: {}", e))?;
+    }
+
+    ec_controller.reopen()?;
+
+    // A read that works proves the new handle is usable
+    ec_controller.check_io()
+}
+
+// Writes the saved config to the EC and applies the active schedule, returning the schedule and the
+// restored state the automation task starts from
+async fn restore_saved_state(
+    ec_controller: &EcController,
+    config: &Arc<Mutex<ServerConfig>>,
+) -> (restore::RestoreReport, Option<schedules::Schedule>, Option<profiles::Profile>) {
+    // Restoring takes a while, work on a copy instead of holding the lock across EC operations
+    let config_guard = config.lock().unwrap().clone();
+    log::info!("Restoring saved parameters from configuration (policy: {:?})...", config_guard.restore_policy);
+    
+    // User presets are only checked when used, point out broken ones early
+    for (name, set) in &config_guard.curve_presets {
+        if let Err(e) = presets::validate_user_preset(name, set) {
+            log::warn!("Ignoring curve preset {} from config: {}", name, e);
+        }
+    }
+    
+    let mut restore_report = restore::restore_config(ec_controller, &config_guard).await;
+    
+    // Apply the schedule window that is active right now, remembering the restored state to return to
+    let startup_schedule = schedules::active(&config_guard.schedules, chrono::Local::now().naive_local())
+        .map(|(_, schedule)| schedule.clone());
+    let mut schedule_fallback = None;
+    if let Some(ref schedule) = startup_schedule {
+        if let Ok(EcResult::Profile(state)) = ec_controller.execute_operation(EcOperation::GetProfileState).await {
+            schedule_fallback = Some(state);
+        }
+        
+        let step = format!("schedule {}-{} profile {}", schedule.start, schedule.end, schedule.profile);
+        match config_guard.profiles.get(&schedule.profile) {
+            Some(profile) => {
+                match ec_controller.execute_operation(EcOperation::ApplyProfile(profile.clone())).await {
+                    Ok(_) => restore_report.record(&step, restore::StepStatus::Ok, None),
+                    Err(e) => restore_report.record(&step, restore::StepStatus::Failed, Some(e)),
+                }
+            }
+            None => restore_report.record(&step, restore::StepStatus::Failed, Some("unknown profile".to_string())),
+        }
+    }
+    
+    match restore_report.failed() {
+        0 => log::info!("Parameter restoration completed"),
+        failed => log::warn!("Parameter restoration completed, {} steps failed", failed),
+    }
+    
+    (restore_report, startup_schedule, schedule_fallback)
+}
+
+// Handler functions
+async fn handle_rejection(rejection: warp::Rejection) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+    match rejection.find::<availability::EcUnavailable>() {
+        Some(unavailable) => Ok(warp::reply::with_status(
+            warp::reply::json(&EcUnavailableResponse {
+                error: unavailable.error.clone(),
+                ec: unavailable.ec.clone(),
+            }),
+            warp::http::StatusCode::SERVICE_UNAVAILABLE,
+        )),
+        None => Err(rejection),
+    }
+}
+
+async fn handle_status(
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
+    ec_state: Arc<availability::EcState>,
+    restore_report: Arc<Mutex<restore::RestoreReport>>,
+    config_fallback: Option<config::ConfigFallback>,
+    reload_status: Arc<Mutex<Option<reload::ReloadStatus>>>,
+) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let config_corrupt = config_fallback.is_some();
+    let config_reload = reload_status.lock().unwrap().clone();
+    let ec = ec_state.status();
+    let restore = restore_report.lock().unwrap().clone();
+    
+    // Running degraded, report why instead of asking the EC
+    if !ec.available {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&StatusResponse {
+                status: 0,
+                version: None,
+                ec,
+                restore,
+                config_corrupt,
+                config_fallback,
+                config_reload,
+            }),
+            warp::http::StatusCode::OK,
+        ));
+    }
+    
+    if ec_queue.send((EcOperation::GetFirmwareVersion, tx)).is_err() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "EC queue unavailable".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    }
+
+    match rx.await {
+        Ok(Ok(EcResult::FirmwareVersion { major, minor })) => {
+            let version = if minor < 10 {
+                format!("{}.0{}", major, minor)
+            } else {
+                format!("{}.{}", major, minor)
+            };
+            
+            log::debug!("Status check: EC firmware version {}", version);
+            
+            Ok(warp::reply::with_status(
+                warp::reply::json(&StatusResponse {
+                    status: 1,
+                    version: Some(version),
+                    ec,
+                    restore,
+                    config_corrupt,
+                    config_fallback,
+                    config_reload,
+                }),
+                warp::http::StatusCode::OK,
+            ))
+        }
+        Ok(Err(e)) => {
+            log::warn!("Status check failed: {}", e);
+            
+            Ok(warp::reply::with_status(
+                warp::reply::json(&StatusResponse {
+                    status: 0,
+                    version: None,
+                    ec,
+                    restore,
+                    config_corrupt,
+                    config_fallback,
+                    config_reload,
+                }),
+                warp::http::StatusCode::OK,
+            ))
+        }
+        Ok(Ok(_)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Unexpected response type".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Err(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Communication timeout".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    }
+}
+
+async fn handle_apu_power_mode_get(
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
+) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    
+    if ec_queue.send((EcOperation::GetApuPowerMode, tx)).is_err() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "EC queue unavailable".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    }
+
+    match rx.await {
+        Ok(Ok(EcResult::ApuPowerMode(mode))) => {
+            log::debug!("APU power mode get: {}", mode);
+            
+            Ok(warp::reply::with_status(
+                warp::reply::json(&PowerModeResponse { power_mode: mode, override_id: None, change_id: None }),
+                warp::http::StatusCode::OK,
+            ))
+        }
+        Ok(Err(e)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Ok(Ok(_)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Unexpected response type".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Err(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Communication timeout".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    }
+}
+
+async fn handle_apu_power_mode_post(
+    request: PowerModeRequest,
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
+    config: Arc<Mutex<ServerConfig>>,
+    overrides: Arc<Mutex<overrides::Overrides>>,
+) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+    let setting = overrides::Setting::PowerMode { power_mode: request.power_mode.clone() };
+    let temporary = match prepare_override(&ec_queue, &setting, request.duration_seconds, request.confirm_timeout_seconds).await {
+        Ok(temporary) => temporary,
+        Err(e) => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::BAD_REQUEST,
+        )),
+    };
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    
+    if ec_queue.send((EcOperation::SetApuPowerMode(request.power_mode.clone()), tx)).is_err() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "EC queue unavailable".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    }
+
+    match rx.await {
+        Ok(Ok(EcResult::ApuPowerMode(mode))) => {
+            let old_mode = config.lock().unwrap().apu_power_mode.clone().unwrap_or_else(|| "unknown".to_string());
+            log::info!(event = "power_mode_set", old_mode = old_mode.as_str(), new_mode = mode.as_str(); "APU power mode set to: {}", mode);
+            
+            // Timed overrides and pending changes are reverted later and not saved to config
+            if let Some(temporary) = temporary {
+                let entry = start_override(&overrides, setting, temporary);
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&PowerModeResponse { power_mode: mode, override_id: entry.override_id(), change_id: entry.change_id() }),
+                    warp::http::StatusCode::OK,
+                ));
+            }
+            
+            // Save to config
+            {
+                let mut config_guard = config.lock().unwrap();
+                config_guard.apu_power_mode = Some(mode.clone());
+                if let Err(e) = config_guard.save() {
+                    log::warn!("Failed to save APU power mode to config: {}", e);
+                }
+            }
+            
+            Ok(warp::reply::with_status(
+                warp::reply::json(&PowerModeResponse { power_mode: mode, override_id: None, change_id: None }),
+                warp::http::StatusCode::OK,
+            ))
+        }
+        Ok(Err(e)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::BAD_REQUEST,
+        )),
+        Ok(Ok(_)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Unexpected response type".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Err(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Communication timeout".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    }
+}
+
+async fn handle_apu_temp(
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
+) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    
+    if ec_queue.send((EcOperation::GetApuTemperature, tx)).is_err() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "EC queue unavailable".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    }
+
+    match rx.await {
+        Ok(Ok(EcResult::ApuTemperature(temp))) => {
+            log::debug!("APU temperature: {}°C", temp);
+            
+            Ok(warp::reply::with_status(
+                warp::reply::json(&TemperatureResponse { temperature: temp }),
+                warp::http::StatusCode::OK,
+            ))
+        }
+        Ok(Err(e)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Ok(Ok(_)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Unexpected response type".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Err(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Communication timeout".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    }
+}
+
+async fn handle_metrics(
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
+) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+    log::debug!("Metrics request received");
+
+    // Helper function to execute EC operation
+    let execute_operation = |operation: EcOperation| async {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        if ec_queue.send((operation, tx)).is_err() {
+            return Err("EC queue unavailable".to_string());
+        }
+        match rx.await {
+            Ok(result) => result,
+            Err(_) => Err("Communication timeout".to_string()),
+        }
+    };
+
+    // Get power mode
+    let power_mode = match execute_operation(EcOperation::GetApuPowerMode).await {
+        Ok(EcResult::ApuPowerMode(mode)) => mode,
+        Ok(_) => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: "Unexpected response type for power mode".to_string() }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Err(e) => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: format!("Failed to get power mode: {}", e) }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    };
+
+    // Get temperature
+    let temperature = match execute_operation(EcOperation::GetApuTemperature).await {
+        Ok(EcResult::ApuTemperature(temp)) => temp,
+        Ok(_) => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: "Unexpected response type for temperature".to_string() }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Err(e) => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: format!("Failed to get temperature: {}", e) }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    };
+
+    // Helper function to get fan metrics
+    let get_fan_metrics = |fan_id: u8| async move {
+        // Get fan mode
+        let mode = match execute_operation(EcOperation::GetFanMode(fan_id)).await {
+            Ok(EcResult::FanMode(mode)) => mode,
+            _ => return Err(format!("Failed to get Fan{} mode", fan_id)),
+        };
+
+        // Get fan level
+        let level = match execute_operation(EcOperation::GetFanLevel(fan_id)).await {
+            Ok(EcResult::FanLevel(level)) => level,
+            _ => return Err(format!("Failed to get Fan{} level", fan_id)),
+        };
+
+        // Get fan RPM
+        let rpm = match execute_operation(EcOperation::GetFanRpm(fan_id)).await {
+            Ok(EcResult::FanRpm(rpm)) => rpm,
+            _ => return Err(format!("Failed to get Fan{} RPM", fan_id)),
+        };
+
+        // Get the curves in use and the power mode curve set they come from
+        let (active_curve_set, curves) = match execute_operation(EcOperation::GetFanActiveCurves(fan_id)).await {
+            Ok(EcResult::FanActiveCurves { curve_set, curves }) => (curve_set, curves),
+            _ => return Err(format!("Failed to get Fan{} active curves", fan_id)),
+        };
+
+        Ok(FanMetrics {
+            mode,
+            level,
+            rpm,
+            rampup_curve: curves.rampup_curve,
+            rampdown_curve: curves.rampdown_curve,
+            active_curve_set,
+        })
+    };
+
+    // Get metrics for all fans
+    let fan1 = match get_fan_metrics(1).await {
+        Ok(metrics) => metrics,
+        Err(e) => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    };
+
+    let fan2 = match get_fan_metrics(2).await {
+        Ok(metrics) => metrics,
+        Err(e) => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    };
+
+    let fan3 = match get_fan_metrics(3).await {
+        Ok(metrics) => metrics,
+        Err(e) => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    };
+
+    let metrics = MetricsResponse {
+        power_mode,
+        temperature,
+        fan1,
+        fan2,
+        fan3,
+    };
+
+    log::debug!("Metrics response prepared successfully");
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&metrics),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+async fn handle_fan_rpm(
+    fan_id: u8,
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
+) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    
+    if ec_queue.send((EcOperation::GetFanRpm(fan_id), tx)).is_err() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "EC queue unavailable".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    }
+
+    match rx.await {
+        Ok(Ok(EcResult::FanRpm(rpm))) => {
+            log::debug!("Fan{} RPM: {}", fan_id, rpm);
+            
+            Ok(warp::reply::with_status(
+                warp::reply::json(&FanRpmResponse { rpm }),
+                warp::http::StatusCode::OK,
+            ))
+        }
+        Ok(Err(e)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Ok(Ok(_)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Unexpected response type".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Err(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Communication timeout".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    }
+}
+
+async fn handle_fan_mode_get(
+    fan_id: u8,
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
+) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    
+    if ec_queue.send((EcOperation::GetFanMode(fan_id), tx)).is_err() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "EC queue unavailable".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    }
+
+    match rx.await {
+        Ok(Ok(EcResult::FanMode(mode))) => {
+            log::debug!("Fan{} mode: {}", fan_id, mode);
+            
+            Ok(warp::reply::with_status(
+                warp::reply::json(&FanModeResponse { mode, override_id: None, change_id: None }),
+                warp::http::StatusCode::OK,
+            ))
+        }
+        Ok(Err(e)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Ok(Ok(_)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Unexpected response type".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Err(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Communication timeout".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    }
+}
+
+async fn handle_fan_mode_post(
+    request: FanModeRequest,
+    fan_id: u8,
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
+    config: Arc<Mutex<ServerConfig>>,
+    overrides: Arc<Mutex<overrides::Overrides>>,
+) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+    let setting = overrides::Setting::FanMode { fan: fan_id, mode: request.mode.clone() };
+    let temporary = match prepare_override(&ec_queue, &setting, request.duration_seconds, request.confirm_timeout_seconds).await {
+        Ok(temporary) => temporary,
+        Err(e) => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::BAD_REQUEST,
+        )),
+    };
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    
+    if ec_queue.send((EcOperation::SetFanMode(fan_id, request.mode.clone()), tx)).is_err() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "EC queue unavailable".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    }
+
+    match rx.await {
+        Ok(Ok(EcResult::FanMode(mode))) => {
+            let old_mode = config.lock().unwrap().fan(fan_id).map(|fan| fan.mode.clone()).unwrap_or_else(|| "unknown".to_string());
+            log::info!(event = "fan_mode_set", fan_id = fan_id, old_mode = old_mode.as_str(), new_mode = mode.as_str();
+                "Fan{} mode set to: {}", fan_id, mode);
+            
+            // Timed overrides and pending changes are reverted later and not saved to config
+            if let Some(temporary) = temporary {
+                let entry = start_override(&overrides, setting, temporary);
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&FanModeResponse { mode, override_id: entry.override_id(), change_id: entry.change_id() }),
+                    warp::http::StatusCode::OK,
+                ));
+            }
+            
+            // Save to config
+            {
+                let mut config_guard = config.lock().unwrap();
+                let fan_config_opt = match fan_id {
+                    1 => &mut config_guard.fan1,
+                    2 => &mut config_guard.fan2,
+                    3 => &mut config_guard.fan3,
+                    _ => return Ok(warp::reply::with_status(
+                        warp::reply::json(&ErrorResponse { error: "Invalid fan ID".to_string() }),
+                        warp::http::StatusCode::BAD_REQUEST,
+                    )),
+                };
+                
+                // Create fan config if it doesn't exist
+                if fan_config_opt.is_none() {
+                    *fan_config_opt = Some(config::FanConfig::default());
+                }
+                
+                if let Some(fan_config) = fan_config_opt {
+                    fan_config.mode = mode.clone();
+                    if let Err(e) = config_guard.save() {
+                        log::warn!("Failed to save Fan{} mode to config: {}", fan_id, e);
+                    }
+                }
+            }
+            
+            Ok(warp::reply::with_status(
+                warp::reply::json(&FanModeResponse { mode, override_id: None, change_id: None }),
+                warp::http::StatusCode::OK,
+            ))
+        }
+        Ok(Err(e)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::BAD_REQUEST,
+        )),
+        Ok(Ok(_)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Unexpected response type".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Err(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Communication timeout".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    }
+}
+
+// Curve handler functions
+async fn handle_fan_rampup_curve_get(
+    fan_id: u8,
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
+) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    
+    if ec_queue.send((EcOperation::GetFanRampupCurve(fan_id), tx)).is_err() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "EC queue unavailable".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    }
+
+    match rx.await {
+        Ok(Ok(EcResult::FanRampupCurve(curve))) => {
+            log::debug!("Fan{} rampup curve get: {:?}", fan_id, curve);
+            
+            Ok(warp::reply::with_status(
+                warp::reply::json(&FanCurveResponse { curve, override_id: None, change_id: None }),
+                warp::http::StatusCode::OK,
+            ))
+        }
+        Ok(Err(e)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Ok(Ok(_)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Unexpected response type".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Err(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Communication timeout".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    }
+}
+
+async fn handle_fan_rampup_curve_post(
+    request: FanCurveRequest,
+    fan_id: u8,
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
+    config: Arc<Mutex<ServerConfig>>,
+    overrides: Arc<Mutex<overrides::Overrides>>,
+) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+    let setting = overrides::Setting::RampupCurve { fan: fan_id, curve: request.curve };
+    let temporary = match prepare_override(&ec_queue, &setting, request.duration_seconds, request.confirm_timeout_seconds).await {
+        Ok(temporary) => temporary,
+        Err(e) => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::BAD_REQUEST,
+        )),
+    };
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    
+    if ec_queue.send((EcOperation::SetFanRampupCurve(fan_id, request.curve), tx)).is_err() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "EC queue unavailable".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    }
+
+    match rx.await {
+        Ok(Ok(EcResult::FanRampupCurve(curve))) => {
+            let old_curve = saved_value(config.lock().unwrap().fan(fan_id).map(|fan| fan.rampup_curve));
+            log::info!(event = "fan_curve_set", fan_id = fan_id, curve = "rampup", old_curve = old_curve.as_str(), new_curve:? = curve;
+                "Fan{} rampup curve set to: {:?}", fan_id, curve);
+            
+            // Timed overrides and pending changes are reverted later and not saved to config
+            if let Some(temporary) = temporary {
+                let entry = start_override(&overrides, setting, temporary);
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&FanCurveResponse { curve, override_id: entry.override_id(), change_id: entry.change_id() }),
+                    warp::http::StatusCode::OK,
+                ));
+            }
+            
+            // Save to config
+            {
+                let mut config_guard = config.lock().unwrap();
+                let fan_config_opt = match fan_id {
+                    1 => &mut config_guard.fan1,
+                    2 => &mut config_guard.fan2,
+                    3 => &mut config_guard.fan3,
+                    _ => return Ok(warp::reply::with_status(
+                        warp::reply::json(&ErrorResponse { error: "Invalid fan ID".to_string() }),
+                        warp::http::StatusCode::BAD_REQUEST,
+                    )),
+                };
+                
+                // Create fan config if it doesn't exist
+                if fan_config_opt.is_none() {
+                    *fan_config_opt = Some(config::FanConfig::default());
+                }
+                
+                if let Some(fan_config) = fan_config_opt {
+                    fan_config.rampup_curve = curve;
+                    if let Err(e) = config_guard.save() {
+                        log::warn!("Failed to save Fan{} rampup curve to config: {}", fan_id, e);
+                    }
+                }
+            }
+            
+            Ok(warp::reply::with_status(
+                warp::reply::json(&FanCurveResponse { curve, override_id: None, change_id: None }),
+                warp::http::StatusCode::OK,
+            ))
+        }
+        Ok(Err(e)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::BAD_REQUEST,
+        )),
+        Ok(Ok(_)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Unexpected response type".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Err(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Communication timeout".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    }
+}
+
+async fn handle_fan_rampdown_curve_get(
+    fan_id: u8,
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
+) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    
+    if ec_queue.send((EcOperation::GetFanRampdownCurve(fan_id), tx)).is_err() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "EC queue unavailable".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    }
+
+    match rx.await {
+        Ok(Ok(EcResult::FanRampdownCurve(curve))) => {
+            log::debug!("Fan{} rampdown curve get: {:?}", fan_id, curve);
+            
+            Ok(warp::reply::with_status(
+                warp::reply::json(&FanCurveResponse { curve, override_id: None, change_id: None }),
+                warp::http::StatusCode::OK,
+            ))
+        }
+        Ok(Err(e)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Ok(Ok(_)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Unexpected response type".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Err(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Communication timeout".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    }
+}
+
+async fn handle_fan_rampdown_curve_post(
+    request: FanCurveRequest,
+    fan_id: u8,
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
+    config: Arc<Mutex<ServerConfig>>,
+    overrides: Arc<Mutex<overrides::Overrides>>,
+) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+    let setting = overrides::Setting::RampdownCurve { fan: fan_id, curve: request.curve };
+    let temporary = match prepare_override(&ec_queue, &setting, request.duration_seconds, request.confirm_timeout_seconds).await {
+        Ok(temporary) => temporary,
+        Err(e) => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::BAD_REQUEST,
+        )),
+    };
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    
+    if ec_queue.send((EcOperation::SetFanRampdownCurve(fan_id, request.curve), tx)).is_err() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "EC queue unavailable".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    }
+
+    match rx.await {
+        Ok(Ok(EcResult::FanRampdownCurve(curve))) => {
+            let old_curve = saved_value(config.lock().unwrap().fan(fan_id).map(|fan| fan.rampdown_curve));
+            log::info!(event = "fan_curve_set", fan_id = fan_id, curve = "rampdown", old_curve = old_curve.as_str(), new_curve:? = curve;
+                "Fan{} rampdown curve set to: {:?}", fan_id, curve);
+            
+            // Timed overrides and pending changes are reverted later and not saved to config
+            if let Some(temporary) = temporary {
+                let entry = start_override(&overrides, setting, temporary);
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&FanCurveResponse { curve, override_id: entry.override_id(), change_id: entry.change_id() }),
+                    warp::http::StatusCode::OK,
+                ));
+            }
+            
+            // Save to config
+            {
+                let mut config_guard = config.lock().unwrap();
+                let fan_config_opt = match fan_id {
+                    1 => &mut config_guard.fan1,
+                    2 => &mut config_guard.fan2,
+                    3 => &mut config_guard.fan3,
+                    _ => return Ok(warp::reply::with_status(
+                        warp::reply::json(&ErrorResponse { error: "Invalid fan ID".to_string() }),
+                        warp::http::StatusCode::BAD_REQUEST,
+                    )),
+                };
+                
+                // Create fan config if it doesn't exist
+                if fan_config_opt.is_none() {
+                    *fan_config_opt = Some(config::FanConfig::default());
+                }
+                
+                if let Some(fan_config) = fan_config_opt {
+                    fan_config.rampdown_curve = curve;
+                    if let Err(e) = config_guard.save() {
+                        log::warn!("Failed to save Fan{} rampdown curve to config: {}", fan_id, e);
+                    }
+                }
+            }
+            
+            Ok(warp::reply::with_status(
+                warp::reply::json(&FanCurveResponse { curve, override_id: None, change_id: None }),
+                warp::http::StatusCode::OK,
+            ))
+        }
+        Ok(Err(e)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::BAD_REQUEST,
+        )),
+        Ok(Ok(_)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Unexpected response type".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Err(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Communication timeout".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    }
+}
+
+async fn handle_fan_target_get(
+    fan_id: u8,
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    
+    if ec_queue.send((EcOperation::GetFanTarget(fan_id), tx)).is_err() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "EC queue unavailable".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    }
+
+    match rx.await {
+        Ok(Ok(EcResult::FanTarget(settings))) => {
+            log::debug!("Fan{} target settings get: {:?}", fan_id, settings);
+            
+            Ok(warp::reply::with_status(
+                warp::reply::json(&settings),
+                warp::http::StatusCode::OK,
+            ))
+        }
+        Ok(Err(e)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Ok(Ok(_)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Unexpected response type".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Err(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Communication timeout".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    }
+}
+
+async fn handle_fan_target_post(
+    request: FanTargetRequest,
+    fan_id: u8,
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
+    config: Arc<Mutex<ServerConfig>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    // Start from the current settings, so only the provided fields change
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    
+    if ec_queue.send((EcOperation::GetFanTarget(fan_id), tx)).is_err() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "EC queue unavailable".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    }
+    
+    let mut settings = match rx.await {
+        Ok(Ok(EcResult::FanTarget(settings))) => settings,
+        Ok(Err(e)) => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::BAD_REQUEST,
+        )),
+        _ => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: format!("Failed to get Fan{} target settings", fan_id),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    };
+    
+    if let Some(target_temperature) = request.target_temperature {
+        settings.target_temperature = target_temperature;
+    }
+    if let Some(kp) = request.kp {
+        settings.kp = kp;
+    }
+    if let Some(ki) = request.ki {
+        settings.ki = ki;
+    }
+    if let Some(kd) = request.kd {
+        settings.kd = kd;
+    }
+    if let Some(min_level) = request.min_level {
+        settings.min_level = min_level;
+    }
+    if let Some(max_level) = request.max_level {
+        settings.max_level = max_level;
+    }
+    
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    
+    if ec_queue.send((EcOperation::SetFanTarget(fan_id, settings), tx)).is_err() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "EC queue unavailable".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    }
+
+    match rx.await {
+        Ok(Ok(EcResult::FanTarget(settings))) => {
+            let old_target = saved_value(config.lock().unwrap().fan(fan_id).map(|fan| fan.target));
+            log::info!(event = "fan_target_set", fan_id = fan_id, old_target = old_target.as_str(), new_target:? = settings;
+                "Fan{} target settings set to: {:?}", fan_id, settings);
+            
+            // Save to config
+            {
+                let mut config_guard = config.lock().unwrap();
+                let fan_config_opt = match fan_id {
+                    1 => &mut config_guard.fan1,
+                    2 => &mut config_guard.fan2,
+                    3 => &mut config_guard.fan3,
+                    _ => return Ok(warp::reply::with_status(
+                        warp::reply::json(&ErrorResponse { error: "Invalid fan ID".to_string() }),
+                        warp::http::StatusCode::BAD_REQUEST,
+                    )),
+                };
+                
+                // Create fan config if it doesn't exist
+                if fan_config_opt.is_none() {
+                    *fan_config_opt = Some(config::FanConfig::default());
+                }
+                
+                if let Some(fan_config) = fan_config_opt {
+                    fan_config.target = settings;
+                    if let Err(e) = config_guard.save() {
+                        log::warn!("Failed to save Fan{} target settings to config: {}", fan_id, e);
+                    }
+                }
+            }
+            
+            Ok(warp::reply::with_status(
+                warp::reply::json(&settings),
+                warp::http::StatusCode::OK,
+            ))
+        }
+        Ok(Err(e)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::BAD_REQUEST,
+        )),
+        Ok(Ok(_)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Unexpected response type".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Err(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Communication timeout".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
     }
 }
 
-// Handler functions
-async fn handle_status(
-    logger: Arc<Mutex<Logger>>,
-    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
-) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+async fn handle_fan_limits_get(
+    fan_id: u8,
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
     let (tx, rx) = tokio::sync::oneshot::channel();
     
-    if ec_queue.send((EcOperation::GetFirmwareVersion, tx)).is_err() {
+    if ec_queue.send((EcOperation::GetFanLimits(fan_id), tx)).is_err() {
         return Ok(warp::reply::with_status(
             warp::reply::json(&ErrorResponse {
                 error: "EC queue unavailable".to_string(),
@@ -906,40 +3052,92 @@ async fn handle_status(
     }
 
     match rx.await {
-        Ok(Ok(EcResult::FirmwareVersion { major, minor })) => {
-            let version = if minor < 10 {
-                format!("{}.0{}", major, minor)
-            } else {
-                format!("{}.{}", major, minor)
-            };
-            
-            {
-                let mut log = logger.lock().unwrap();
-                log.info(&format!("Status check: EC firmware version {}", version));
-            }
+        Ok(Ok(EcResult::FanLimits(limits))) => {
+            log::debug!("Fan{} level limits get: {:?}", fan_id, limits);
             
             Ok(warp::reply::with_status(
-                warp::reply::json(&StatusResponse {
-                    status: 1,
-                    version: Some(version),
-                }),
+                warp::reply::json(&limits),
                 warp::http::StatusCode::OK,
             ))
         }
-        Ok(Err(e)) => {
+        Ok(Err(e)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Ok(Ok(_)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Unexpected response type".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+        Err(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Communication timeout".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    }
+}
+
+// Replaces all limits of the fan, omitted fields fall back to no limit
+async fn handle_fan_limits_post(
+    limits: limits::FanLimits,
+    fan_id: u8,
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
+    config: Arc<Mutex<ServerConfig>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    
+    if ec_queue.send((EcOperation::SetFanLimits(fan_id, limits), tx)).is_err() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "EC queue unavailable".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    }
+
+    match rx.await {
+        Ok(Ok(EcResult::FanLimits(limits))) => {
+            let old_limits = saved_value(config.lock().unwrap().fan(fan_id).map(|fan| fan.limits));
+            log::info!(event = "fan_limits_set", fan_id = fan_id, old_limits = old_limits.as_str(), new_limits:? = limits;
+                "Fan{} level limits set to: {:?}", fan_id, limits);
+            
+            // Save to config
             {
-                let mut log = logger.lock().unwrap();
-                log.warn(&format!("Status check failed: {}", e));
+                let mut config_guard = config.lock().unwrap();
+                let fan_config_opt = match fan_id {
+                    1 => &mut config_guard.fan1,
+                    2 => &mut config_guard.fan2,
+                    3 => &mut config_guard.fan3,
+                    _ => return Ok(warp::reply::with_status(
+                        warp::reply::json(&ErrorResponse { error: "Invalid fan ID".to_string() }),
+                        warp::http::StatusCode::BAD_REQUEST,
+                    )),
+                };
+                
+                // Create fan config if it doesn't exist
+                if fan_config_opt.is_none() {
+                    *fan_config_opt = Some(config::FanConfig::default());
+                }
+                
+                if let Some(fan_config) = fan_config_opt {
+                    fan_config.limits = limits;
+                    if let Err(e) = config_guard.save() {
+                        log::warn!("Failed to save Fan{} level limits to config: {}", fan_id, e);
+                    }
+                }
             }
             
             Ok(warp::reply::with_status(
-                warp::reply::json(&StatusResponse {
-                    status: 0,
-                    version: None,
-                }),
+                warp::reply::json(&limits),
                 warp::http::StatusCode::OK,
             ))
         }
+        Ok(Err(e)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::BAD_REQUEST,
+        )),
         Ok(Ok(_)) => Ok(warp::reply::with_status(
             warp::reply::json(&ErrorResponse {
                 error: "Unexpected response type".to_string(),
@@ -955,13 +3153,13 @@ async fn handle_status(
     }
 }
 
-async fn handle_apu_power_mode_get(
-    logger: Arc<Mutex<Logger>>,
-    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
-) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+async fn handle_fan_power_mode_curves_get(
+    fan_id: u8,
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
     let (tx, rx) = tokio::sync::oneshot::channel();
     
-    if ec_queue.send((EcOperation::GetApuPowerMode, tx)).is_err() {
+    if ec_queue.send((EcOperation::GetFanPowerModeCurves(fan_id), tx)).is_err() {
         return Ok(warp::reply::with_status(
             warp::reply::json(&ErrorResponse {
                 error: "EC queue unavailable".to_string(),
@@ -971,14 +3169,11 @@ async fn handle_apu_power_mode_get(
     }
 
     match rx.await {
-        Ok(Ok(EcResult::ApuPowerMode(mode))) => {
-            {
-                let mut log = logger.lock().unwrap();
-                log.info(&format!("APU power mode get: {}", mode));
-            }
+        Ok(Ok(EcResult::FanPowerModeCurves(sets))) => {
+            log::debug!("Fan{} power mode curve sets get: {:?}", fan_id, sets);
             
             Ok(warp::reply::with_status(
-                warp::reply::json(&PowerModeResponse { power_mode: mode }),
+                warp::reply::json(&sets),
                 warp::http::StatusCode::OK,
             ))
         }
@@ -1001,15 +3196,15 @@ async fn handle_apu_power_mode_get(
     }
 }
 
-async fn handle_apu_power_mode_post(
-    request: PowerModeRequest,
-    logger: Arc<Mutex<Logger>>,
-    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
+async fn handle_fan_power_mode_curves_post(
+    request: std::collections::BTreeMap<String, curve::FanCurveSet>,
+    fan_id: u8,
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
     config: Arc<Mutex<ServerConfig>>,
-) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+) -> Result<impl warp::Reply, warp::Rejection> {
     let (tx, rx) = tokio::sync::oneshot::channel();
     
-    if ec_queue.send((EcOperation::SetApuPowerMode(request.power_mode.clone()), tx)).is_err() {
+    if ec_queue.send((EcOperation::SetFanPowerModeCurves(fan_id, request), tx)).is_err() {
         return Ok(warp::reply::with_status(
             warp::reply::json(&ErrorResponse {
                 error: "EC queue unavailable".to_string(),
@@ -1019,24 +3214,39 @@ async fn handle_apu_power_mode_post(
     }
 
     match rx.await {
-        Ok(Ok(EcResult::ApuPowerMode(mode))) => {
-            {
-                let mut log = logger.lock().unwrap();
-                log.info(&format!("APU power mode set to: {}", mode));
-            }
+        Ok(Ok(EcResult::FanPowerModeCurves(sets))) => {
+            let old_sets = saved_value(config.lock().unwrap().fan(fan_id).map(|fan| fan.power_mode_curves.clone()));
+            log::info!(event = "fan_power_mode_curves_set", fan_id = fan_id, old_sets = old_sets.as_str(), new_sets:? = sets;
+                "Fan{} power mode curve sets set to: {:?}", fan_id, sets);
             
             // Save to config
             {
                 let mut config_guard = config.lock().unwrap();
-                config_guard.apu_power_mode = Some(mode.clone());
-                if let Err(e) = config_guard.save() {
-                    let mut log = logger.lock().unwrap();
-                    log.warn(&format!("Failed to save APU power mode to config: {}", e));
+                let fan_config_opt = match fan_id {
+                    1 => &mut config_guard.fan1,
+                    2 => &mut config_guard.fan2,
+                    3 => &mut config_guard.fan3,
+                    _ => return Ok(warp::reply::with_status(
+                        warp::reply::json(&ErrorResponse { error: "Invalid fan ID".to_string() }),
+                        warp::http::StatusCode::BAD_REQUEST,
+                    )),
+                };
+                
+                // Create fan config if it doesn't exist
+                if fan_config_opt.is_none() {
+                    *fan_config_opt = Some(config::FanConfig::default());
+                }
+                
+                if let Some(fan_config) = fan_config_opt {
+                    fan_config.power_mode_curves = sets.clone();
+                    if let Err(e) = config_guard.save() {
+                        log::warn!("Failed to save Fan{} power mode curve sets to config: {}", fan_id, e);
+                    }
                 }
             }
             
             Ok(warp::reply::with_status(
-                warp::reply::json(&PowerModeResponse { power_mode: mode }),
+                warp::reply::json(&sets),
                 warp::http::StatusCode::OK,
             ))
         }
@@ -1059,13 +3269,13 @@ async fn handle_apu_power_mode_post(
     }
 }
 
-async fn handle_apu_temp(
-    logger: Arc<Mutex<Logger>>,
-    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
-) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+async fn handle_fan_temperature_source_get(
+    fan_id: u8,
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
     let (tx, rx) = tokio::sync::oneshot::channel();
     
-    if ec_queue.send((EcOperation::GetApuTemperature, tx)).is_err() {
+    if ec_queue.send((EcOperation::GetFanTemperatureSource(fan_id), tx)).is_err() {
         return Ok(warp::reply::with_status(
             warp::reply::json(&ErrorResponse {
                 error: "EC queue unavailable".to_string(),
@@ -1075,14 +3285,11 @@ async fn handle_apu_temp(
     }
 
     match rx.await {
-        Ok(Ok(EcResult::ApuTemperature(temp))) => {
-            {
-                let mut log = logger.lock().unwrap();
-                log.info(&format!("APU temperature: {}°C", temp));
-            }
+        Ok(Ok(EcResult::FanTemperatureSource(source))) => {
+            log::debug!("Fan{} temperature source get: {:?}", fan_id, source);
             
             Ok(warp::reply::with_status(
-                warp::reply::json(&TemperatureResponse { temperature: temp }),
+                warp::reply::json(&source),
                 warp::http::StatusCode::OK,
             ))
         }
@@ -1105,146 +3312,198 @@ async fn handle_apu_temp(
     }
 }
 
-async fn handle_metrics(
-    logger: Arc<Mutex<Logger>>,
-    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
-) -> std::result::Result<impl warp::Reply, warp::Rejection> {
-    {
-        let mut log = logger.lock().unwrap();
-        log.info("Metrics request received");
+async fn handle_fan_temperature_source_post(
+    request: sensors::TemperatureSource,
+    fan_id: u8,
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
+    config: Arc<Mutex<ServerConfig>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    
+    if ec_queue.send((EcOperation::SetFanTemperatureSource(fan_id, request), tx)).is_err() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "EC queue unavailable".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        ));
     }
 
-    // Helper function to execute EC operation
-    let execute_operation = |operation: EcOperation| async {
-        let (tx, rx) = tokio::sync::oneshot::channel();
-        if ec_queue.send((operation, tx)).is_err() {
-            return Err("EC queue unavailable".to_string());
-        }
-        match rx.await {
-            Ok(result) => result,
-            Err(_) => Err("Communication timeout".to_string()),
+    match rx.await {
+        Ok(Ok(EcResult::FanTemperatureSource(source))) => {
+            let old_source = saved_value(config.lock().unwrap().fan(fan_id).map(|fan| fan.temperature_source.clone()));
+            log::info!(event = "fan_temperature_source_set", fan_id = fan_id, old_source = old_source.as_str(), new_source:? = source;
+                "Fan{} temperature source set to: {:?}", fan_id, source);
+            
+            // Save to config
+            {
+                let mut config_guard = config.lock().unwrap();
+                let fan_config_opt = match fan_id {
+                    1 => &mut config_guard.fan1,
+                    2 => &mut config_guard.fan2,
+                    3 => &mut config_guard.fan3,
+                    _ => return Ok(warp::reply::with_status(
+                        warp::reply::json(&ErrorResponse { error: "Invalid fan ID".to_string() }),
+                        warp::http::StatusCode::BAD_REQUEST,
+                    )),
+                };
+                
+                // Create fan config if it doesn't exist
+                if fan_config_opt.is_none() {
+                    *fan_config_opt = Some(config::FanConfig::default());
+                }
+                
+                if let Some(fan_config) = fan_config_opt {
+                    fan_config.temperature_source = source.clone();
+                    if let Err(e) = config_guard.save() {
+                        log::warn!("Failed to save Fan{} temperature source to config: {}", fan_id, e);
+                    }
+                }
+            }
+            
+            Ok(warp::reply::with_status(
+                warp::reply::json(&source),
+                warp::http::StatusCode::OK,
+            ))
         }
-    };
-
-    // Get power mode
-    let power_mode = match execute_operation(EcOperation::GetApuPowerMode).await {
-        Ok(EcResult::ApuPowerMode(mode)) => mode,
-        Ok(_) => return Ok(warp::reply::with_status(
-            warp::reply::json(&ErrorResponse { error: "Unexpected response type for power mode".to_string() }),
+        Ok(Err(e)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::BAD_REQUEST,
+        )),
+        Ok(Ok(_)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Unexpected response type".to_string(),
+            }),
             warp::http::StatusCode::INTERNAL_SERVER_ERROR,
         )),
-        Err(e) => return Ok(warp::reply::with_status(
-            warp::reply::json(&ErrorResponse { error: format!("Failed to get power mode: {}", e) }),
+        Err(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Communication timeout".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    }
+}
+
+async fn handle_sensors_get(
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    
+    if ec_queue.send((EcOperation::GetSensors, tx)).is_err() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "EC queue unavailable".to_string(),
+            }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    }
+
+    match rx.await {
+        Ok(Ok(EcResult::Sensors(list))) => {
+            log::debug!("Sensors get: {} pushed sensors", list.len());
+            
+            let now = chrono::Utc::now();
+            let sensors = list.into_iter()
+                .map(|(name, sensor)| SensorResponse {
+                    name,
+                    temperature: sensor.temperature,
+                    updated: sensor.updated,
+                    expires: sensor.expires,
+                    stale: sensor.is_stale(now),
+                })
+                .collect();
+            
+            Ok(warp::reply::with_status(
+                warp::reply::json(&SensorsResponse { sensors }),
+                warp::http::StatusCode::OK,
+            ))
+        }
+        Ok(Err(e)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
             warp::http::StatusCode::INTERNAL_SERVER_ERROR,
         )),
-    };
-
-    // Get temperature
-    let temperature = match execute_operation(EcOperation::GetApuTemperature).await {
-        Ok(EcResult::ApuTemperature(temp)) => temp,
-        Ok(_) => return Ok(warp::reply::with_status(
-            warp::reply::json(&ErrorResponse { error: "Unexpected response type for temperature".to_string() }),
+        Ok(Ok(_)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Unexpected response type".to_string(),
+            }),
             warp::http::StatusCode::INTERNAL_SERVER_ERROR,
         )),
-        Err(e) => return Ok(warp::reply::with_status(
-            warp::reply::json(&ErrorResponse { error: format!("Failed to get temperature: {}", e) }),
+        Err(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Communication timeout".to_string(),
+            }),
             warp::http::StatusCode::INTERNAL_SERVER_ERROR,
         )),
-    };
-
-    // Helper function to get fan metrics
-    let get_fan_metrics = |fan_id: u8| async move {
-        // Get fan mode
-        let mode = match execute_operation(EcOperation::GetFanMode(fan_id)).await {
-            Ok(EcResult::FanMode(mode)) => mode,
-            _ => return Err(format!("Failed to get Fan{} mode", fan_id)),
-        };
-
-        // Get fan level
-        let level = match execute_operation(EcOperation::GetFanLevel(fan_id)).await {
-            Ok(EcResult::FanLevel(level)) => level,
-            _ => return Err(format!("Failed to get Fan{} level", fan_id)),
-        };
-
-        // Get fan RPM
-        let rpm = match execute_operation(EcOperation::GetFanRpm(fan_id)).await {
-            Ok(EcResult::FanRpm(rpm)) => rpm,
-            _ => return Err(format!("Failed to get Fan{} RPM", fan_id)),
-        };
-
-        // Get rampup curve
-        let rampup_curve = match execute_operation(EcOperation::GetFanRampupCurve(fan_id)).await {
-            Ok(EcResult::FanRampupCurve(curve)) => curve,
-            _ => return Err(format!("Failed to get Fan{} rampup curve", fan_id)),
-        };
-
-        // Get rampdown curve
-        let rampdown_curve = match execute_operation(EcOperation::GetFanRampdownCurve(fan_id)).await {
-            Ok(EcResult::FanRampdownCurve(curve)) => curve,
-            _ => return Err(format!("Failed to get Fan{} rampdown curve", fan_id)),
-        };
+    }
+}
 
-        Ok(FanMetrics {
-            mode,
-            level,
-            rpm,
-            rampup_curve,
-            rampdown_curve,
-        })
+async fn handle_sensor_post(
+    name: String,
+    request: SensorPushRequest,
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
+    config: Arc<Mutex<ServerConfig>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let expiry_seconds = match request.expires_in_seconds {
+        Some(seconds) => seconds,
+        None => {
+            let config_guard = config.lock().unwrap();
+            config_guard.sensor_expiry_seconds.unwrap_or(sensors::DEFAULT_SENSOR_EXPIRY_SECONDS)
+        }
     };
-
-    // Get metrics for all fans
-    let fan1 = match get_fan_metrics(1).await {
-        Ok(metrics) => metrics,
-        Err(e) => return Ok(warp::reply::with_status(
-            warp::reply::json(&ErrorResponse { error: e }),
+    
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    
+    if ec_queue.send((EcOperation::PushSensor(name, request.temperature, expiry_seconds), tx)).is_err() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "EC queue unavailable".to_string(),
+            }),
             warp::http::StatusCode::INTERNAL_SERVER_ERROR,
-        )),
-    };
+        ));
+    }
 
-    let fan2 = match get_fan_metrics(2).await {
-        Ok(metrics) => metrics,
-        Err(e) => return Ok(warp::reply::with_status(
+    match rx.await {
+        Ok(Ok(EcResult::Sensor(name, sensor))) => {
+            log::debug!("Sensor {} pushed: {}°C (expires in {}s)", name, sensor.temperature, expiry_seconds);
+            
+            Ok(warp::reply::with_status(
+                warp::reply::json(&SensorResponse {
+                    name,
+                    temperature: sensor.temperature,
+                    updated: sensor.updated,
+                    expires: sensor.expires,
+                    stale: sensor.is_stale(chrono::Utc::now()),
+                }),
+                warp::http::StatusCode::OK,
+            ))
+        }
+        Ok(Err(e)) => Ok(warp::reply::with_status(
             warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::BAD_REQUEST,
+        )),
+        Ok(Ok(_)) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Unexpected response type".to_string(),
+            }),
             warp::http::StatusCode::INTERNAL_SERVER_ERROR,
         )),
-    };
-
-    let fan3 = match get_fan_metrics(3).await {
-        Ok(metrics) => metrics,
-        Err(e) => return Ok(warp::reply::with_status(
-            warp::reply::json(&ErrorResponse { error: e }),
+        Err(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: "Communication timeout".to_string(),
+            }),
             warp::http::StatusCode::INTERNAL_SERVER_ERROR,
         )),
-    };
-
-    let metrics = MetricsResponse {
-        power_mode,
-        temperature,
-        fan1,
-        fan2,
-        fan3,
-    };
-
-    {
-        let mut log = logger.lock().unwrap();
-        log.info("Metrics response prepared successfully");
     }
-
-    Ok(warp::reply::with_status(
-        warp::reply::json(&metrics),
-        warp::http::StatusCode::OK,
-    ))
 }
 
-async fn handle_fan_rpm(
+async fn handle_fan_level_get(
     fan_id: u8,
-    logger: Arc<Mutex<Logger>>,
-    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
-) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
     let (tx, rx) = tokio::sync::oneshot::channel();
     
-    if ec_queue.send((EcOperation::GetFanRpm(fan_id), tx)).is_err() {
+    if ec_queue.send((EcOperation::GetFanLevel(fan_id), tx)).is_err() {
         return Ok(warp::reply::with_status(
             warp::reply::json(&ErrorResponse {
                 error: "EC queue unavailable".to_string(),
@@ -1254,14 +3513,11 @@ async fn handle_fan_rpm(
     }
 
     match rx.await {
-        Ok(Ok(EcResult::FanRpm(rpm))) => {
-            {
-                let mut log = logger.lock().unwrap();
-                log.info(&format!("Fan{} RPM: {}", fan_id, rpm));
-            }
+        Ok(Ok(EcResult::FanLevel(level))) => {
+            log::debug!("Fan{} level: {}", fan_id, level);
             
             Ok(warp::reply::with_status(
-                warp::reply::json(&FanRpmResponse { rpm }),
+                warp::reply::json(&FanLevelResponse { level, override_id: None, change_id: None, clamped: None }),
                 warp::http::StatusCode::OK,
             ))
         }
@@ -1284,14 +3540,37 @@ async fn handle_fan_rpm(
     }
 }
 
-async fn handle_fan_mode_get(
+async fn handle_fan_level_post(
+    request: FanLevelRequest,
     fan_id: u8,
-    logger: Arc<Mutex<Logger>>,
-    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
-) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
+    config: Arc<Mutex<ServerConfig>>,
+    overrides: Arc<Mutex<overrides::Overrides>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let setting = overrides::Setting::FanLevel { fan: fan_id, level: request.level };
+    let temporary = match prepare_override(&ec_queue, &setting, request.duration_seconds, request.confirm_timeout_seconds).await {
+        Ok(temporary) => temporary,
+        Err(e) => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::BAD_REQUEST,
+        )),
+    };
+
+    // Previous level for the fan_level_set event, from the saved config if the EC read fails
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let old_level = match ec_queue.send((EcOperation::GetFanLevel(fan_id), tx)) {
+        Ok(()) => match rx.await {
+            Ok(Ok(EcResult::FanLevel(level))) => Some(level),
+            _ => None,
+        },
+        Err(_) => None,
+    }
+    .or_else(|| config.lock().unwrap().fan(fan_id).map(|fan| fan.level))
+    .unwrap_or_default();
+
     let (tx, rx) = tokio::sync::oneshot::channel();
     
-    if ec_queue.send((EcOperation::GetFanMode(fan_id), tx)).is_err() {
+    if ec_queue.send((EcOperation::SetFanLevel(fan_id, request.level), tx)).is_err() {
         return Ok(warp::reply::with_status(
             warp::reply::json(&ErrorResponse {
                 error: "EC queue unavailable".to_string(),
@@ -1301,20 +3580,58 @@ async fn handle_fan_mode_get(
     }
 
     match rx.await {
-        Ok(Ok(EcResult::FanMode(mode))) => {
+        Ok(Ok(EcResult::FanLevelSet { level, clamp })) => {
+            match clamp {
+                Some(ref clamp) => log::warn!(event = "fan_level_set", fan_id = fan_id, old_level = old_level, new_level = level;
+                    "Fan{} level {} clamped to {}: {}", fan_id, clamp.requested_level, level, clamp.reason),
+                None => log::info!(event = "fan_level_set", fan_id = fan_id, old_level = old_level, new_level = level; "Fan{} level set to: {}", fan_id, level),
+            }
+            
+            // Timed overrides and pending changes are reverted later and not saved to config
+            if let Some(temporary) = temporary {
+                let setting = overrides::Setting::FanLevel { fan: fan_id, level };
+                let entry = start_override(&overrides, setting, temporary);
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&FanLevelResponse { level, override_id: entry.override_id(), change_id: entry.change_id(), clamped: clamp }),
+                    warp::http::StatusCode::OK,
+                ));
+            }
+            
+            // Save to config
             {
-                let mut log = logger.lock().unwrap();
-                log.info(&format!("Fan{} mode: {}", fan_id, mode));
+                let mut config_guard = config.lock().unwrap();
+                let fan_config_opt = match fan_id {
+                    1 => &mut config_guard.fan1,
+                    2 => &mut config_guard.fan2,
+                    3 => &mut config_guard.fan3,
+                    _ => return Ok(warp::reply::with_status(
+                        warp::reply::json(&ErrorResponse { error: "Invalid fan ID".to_string() }),
+                        warp::http::StatusCode::BAD_REQUEST,
+                    )),
+                };
+                
+                // Create fan config if it doesn't exist
+                if fan_config_opt.is_none() {
+                    *fan_config_opt = Some(config::FanConfig::default());
+                }
+                
+                if let Some(fan_config) = fan_config_opt {
+                    fan_config.level = level;
+                    if let Err(e) = config_guard.save() {
+                        log::warn!("Failed to save Fan{} level to config: {}", fan_id, e);
+                    }
+                }
             }
             
+            
             Ok(warp::reply::with_status(
-                warp::reply::json(&FanModeResponse { mode }),
+                warp::reply::json(&FanLevelResponse { level, override_id: None, change_id: None, clamped: clamp }),
                 warp::http::StatusCode::OK,
             ))
         }
         Ok(Err(e)) => Ok(warp::reply::with_status(
             warp::reply::json(&ErrorResponse { error: e }),
-            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            warp::http::StatusCode::BAD_REQUEST,
         )),
         Ok(Ok(_)) => Ok(warp::reply::with_status(
             warp::reply::json(&ErrorResponse {
@@ -1331,16 +3648,60 @@ async fn handle_fan_mode_get(
     }
 }
 
-async fn handle_fan_mode_post(
-    request: FanModeRequest,
+async fn handle_curve_presets_get(
+    config: Arc<Mutex<ServerConfig>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut presets: Vec<CurvePresetResponse> = presets::BUILTIN_PRESETS.iter()
+        .map(|(name, set)| CurvePresetResponse {
+            name: name.to_string(),
+            builtin: true,
+            rampup_curve: set.rampup_curve,
+            rampdown_curve: set.rampdown_curve,
+        })
+        .collect();
+
+    {
+        let config_guard = config.lock().unwrap();
+        presets.extend(config_guard.curve_presets.iter()
+            .filter(|(name, set)| presets::validate_user_preset(name, set).is_ok())
+            .map(|(name, set)| CurvePresetResponse {
+                name: name.clone(),
+                builtin: false,
+                rampup_curve: set.rampup_curve,
+                rampdown_curve: set.rampdown_curve,
+            }));
+    }
+
+    log::debug!("Curve presets get: {} presets", presets.len());
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&CurvePresetsResponse { presets }),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+async fn handle_fan_curve_preset_post(
     fan_id: u8,
-    logger: Arc<Mutex<Logger>>,
-    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
+    request: CurvePresetApplyRequest,
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
     config: Arc<Mutex<ServerConfig>>,
-) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let preset = {
+        let config_guard = config.lock().unwrap();
+        presets::find(&request.name, &config_guard.curve_presets)
+    };
+
+    let preset = match preset {
+        Some(preset) => preset,
+        None => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: format!("Unknown curve preset: {}", request.name) }),
+            warp::http::StatusCode::NOT_FOUND,
+        )),
+    };
+
     let (tx, rx) = tokio::sync::oneshot::channel();
     
-    if ec_queue.send((EcOperation::SetFanMode(fan_id, request.mode.clone()), tx)).is_err() {
+    if ec_queue.send((EcOperation::SetFanCurves(fan_id, preset), tx)).is_err() {
         return Ok(warp::reply::with_status(
             warp::reply::json(&ErrorResponse {
                 error: "EC queue unavailable".to_string(),
@@ -1350,11 +3711,11 @@ async fn handle_fan_mode_post(
     }
 
     match rx.await {
-        Ok(Ok(EcResult::FanMode(mode))) => {
-            {
-                let mut log = logger.lock().unwrap();
-                log.info(&format!("Fan{} mode set to: {}", fan_id, mode));
-            }
+        Ok(Ok(EcResult::FanCurves(set))) => {
+            let old_curves = saved_value(config.lock().unwrap().fan(fan_id).map(|fan| (fan.rampup_curve, fan.rampdown_curve)));
+            log::info!(event = "fan_preset_set", fan_id = fan_id, preset = request.name.as_str(), old_curves = old_curves.as_str(),
+                new_curves:? = (set.rampup_curve, set.rampdown_curve);
+                "Fan{} curves set from preset {}: rampup {:?}, rampdown {:?}", fan_id, request.name, set.rampup_curve, set.rampdown_curve);
             
             // Save to config
             {
@@ -1375,16 +3736,21 @@ async fn handle_fan_mode_post(
                 }
                 
                 if let Some(fan_config) = fan_config_opt {
-                    fan_config.mode = mode.clone();
+                    fan_config.rampup_curve = set.rampup_curve;
+                    fan_config.rampdown_curve = set.rampdown_curve;
                     if let Err(e) = config_guard.save() {
-                        let mut log = logger.lock().unwrap();
-                        log.warn(&format!("Failed to save Fan{} mode to config: {}", fan_id, e));
+                        log::warn!("Failed to save Fan{} curves to config: {}", fan_id, e);
                     }
                 }
             }
             
             Ok(warp::reply::with_status(
-                warp::reply::json(&FanModeResponse { mode }),
+                warp::reply::json(&CurvePresetResponse {
+                    builtin: presets::builtin(&request.name).is_some(),
+                    name: request.name,
+                    rampup_curve: set.rampup_curve,
+                    rampdown_curve: set.rampdown_curve,
+                }),
                 warp::http::StatusCode::OK,
             ))
         }
@@ -1407,15 +3773,137 @@ async fn handle_fan_mode_post(
     }
 }
 
-// Curve handler functions
-async fn handle_fan_rampup_curve_get(
-    fan_id: u8,
-    logger: Arc<Mutex<Logger>>,
-    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
-) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+async fn handle_profiles_get(
+    config: Arc<Mutex<ServerConfig>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let profiles = {
+        let config_guard = config.lock().unwrap();
+        config_guard.profiles.clone()
+    };
+
+    log::debug!("Profiles get: {:?}", profiles.keys().collect::<Vec<_>>());
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&profiles),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+async fn handle_profile_get(
+    name: String,
+    config: Arc<Mutex<ServerConfig>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let profile = {
+        let config_guard = config.lock().unwrap();
+        config_guard.profiles.get(&name).cloned()
+    };
+
+    match profile {
+        Some(profile) => {
+            log::debug!("Profile {} get: {:?}", name, profile);
+            
+            Ok(warp::reply::with_status(
+                warp::reply::json(&profile),
+                warp::http::StatusCode::OK,
+            ))
+        }
+        None => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: format!("Unknown profile: {}", name) }),
+            warp::http::StatusCode::NOT_FOUND,
+        )),
+    }
+}
+
+async fn handle_profile_post(
+    name: String,
+    request: profiles::Profile,
+    config: Arc<Mutex<ServerConfig>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if let Err(e) = profiles::validate_profile_name(&name).and_then(|_| request.validate()) {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    log::info!("Profile {} set to: {:?}", name, request);
+
+    // Save to config
+    {
+        let mut config_guard = config.lock().unwrap();
+        config_guard.profiles.insert(name.clone(), request.clone());
+        if let Err(e) = config_guard.save() {
+            log::warn!("Failed to save profile {} to config: {}", name, e);
+        }
+    }
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&request),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+async fn handle_profile_delete(
+    name: String,
+    config: Arc<Mutex<ServerConfig>>,
+    schedule_override: Arc<Mutex<Option<schedules::ScheduleOverride>>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut config_guard = config.lock().unwrap();
+    
+    // Deleting it would leave schedules, process rules or the schedule override pointing at nothing
+    let mut users = config_guard.profile_users(&name);
+    if schedule_override.lock().unwrap().as_ref().is_some_and(|schedule_override| schedule_override.profile == name) {
+        users.push("the schedule override".to_string());
+    }
+    if !users.is_empty() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: format!("Profile {} is used by {}", name, users.join(", ")) }),
+            warp::http::StatusCode::CONFLICT,
+        ));
+    }
+    
+    let profile = match config_guard.profiles.remove(&name) {
+        Some(profile) => profile,
+        None => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: format!("Unknown profile: {}", name) }),
+            warp::http::StatusCode::NOT_FOUND,
+        )),
+    };
+
+    log::info!("Profile {} deleted", name);
+
+    if let Err(e) = config_guard.save() {
+        log::warn!("Failed to save config after deleting profile {}: {}", name, e);
+    }
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&profile),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+async fn handle_profile_apply(
+    name: String,
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
+    config: Arc<Mutex<ServerConfig>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let profile = {
+        let config_guard = config.lock().unwrap();
+        config_guard.profiles.get(&name).cloned()
+    };
+
+    let profile = match profile {
+        Some(profile) => profile,
+        None => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: format!("Unknown profile: {}", name) }),
+            warp::http::StatusCode::NOT_FOUND,
+        )),
+    };
+
+    // A single queue operation, so no other request can interleave with the profile
     let (tx, rx) = tokio::sync::oneshot::channel();
     
-    if ec_queue.send((EcOperation::GetFanRampupCurve(fan_id), tx)).is_err() {
+    if ec_queue.send((EcOperation::ApplyProfile(profile), tx)).is_err() {
         return Ok(warp::reply::with_status(
             warp::reply::json(&ErrorResponse {
                 error: "EC queue unavailable".to_string(),
@@ -1425,20 +3913,42 @@ async fn handle_fan_rampup_curve_get(
     }
 
     match rx.await {
-        Ok(Ok(EcResult::FanRampupCurve(curve))) => {
+        Ok(Ok(EcResult::Profile(profile))) => {
+            let old_state = config.lock().unwrap().saved_profile(&profile);
+            log::info!(event = "profile_applied", profile = name.as_str(), old_state:? = old_state, new_state:? = profile;
+                "Profile {} applied: {:?}", name, profile);
+            
+            // Save to config, so the profile's state is restored on restart
             {
-                let mut log = logger.lock().unwrap();
-                log.info(&format!("Fan{} rampup curve get: {:?}", fan_id, curve));
+                let mut config_guard = config.lock().unwrap();
+                let server_config = &mut *config_guard;
+                server_config.apu_power_mode = Some(profile.power_mode.clone());
+                
+                let fan_configs = [&mut server_config.fan1, &mut server_config.fan2, &mut server_config.fan3];
+                for (fan_config_opt, fan) in fan_configs.into_iter().zip(profile.fans()) {
+                    let fan_config = fan_config_opt.get_or_insert_with(config::FanConfig::default);
+                    fan_config.mode = fan.mode.clone();
+                    fan_config.level = fan.level;
+                    fan_config.rampup_curve = fan.rampup_curve;
+                    fan_config.rampdown_curve = fan.rampdown_curve;
+                    if let Some(limits) = fan.limits {
+                        fan_config.limits = limits;
+                    }
+                }
+                
+                if let Err(e) = config_guard.save() {
+                    log::warn!("Failed to save profile {} state to config: {}", name, e);
+                }
             }
             
             Ok(warp::reply::with_status(
-                warp::reply::json(&FanCurveResponse { curve }),
+                warp::reply::json(&profile),
                 warp::http::StatusCode::OK,
             ))
         }
         Ok(Err(e)) => Ok(warp::reply::with_status(
             warp::reply::json(&ErrorResponse { error: e }),
-            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            warp::http::StatusCode::BAD_REQUEST,
         )),
         Ok(Ok(_)) => Ok(warp::reply::with_status(
             warp::reply::json(&ErrorResponse {
@@ -1455,16 +3965,13 @@ async fn handle_fan_rampup_curve_get(
     }
 }
 
-async fn handle_fan_rampup_curve_post(
-    request: FanCurveRequest,
-    fan_id: u8,
-    logger: Arc<Mutex<Logger>>,
-    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
+async fn handle_profile_active_get(
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
     config: Arc<Mutex<ServerConfig>>,
-) -> std::result::Result<impl warp::Reply, warp::Rejection> {
+) -> Result<impl warp::Reply, warp::Rejection> {
     let (tx, rx) = tokio::sync::oneshot::channel();
     
-    if ec_queue.send((EcOperation::SetFanRampupCurve(fan_id, request.curve), tx)).is_err() {
+    if ec_queue.send((EcOperation::GetProfileState, tx)).is_err() {
         return Ok(warp::reply::with_status(
             warp::reply::json(&ErrorResponse {
                 error: "EC queue unavailable".to_string(),
@@ -1474,47 +3981,24 @@ async fn handle_fan_rampup_curve_post(
     }
 
     match rx.await {
-        Ok(Ok(EcResult::FanRampupCurve(curve))) => {
-            {
-                let mut log = logger.lock().unwrap();
-                log.info(&format!("Fan{} rampup curve set to: {:?}", fan_id, curve));
-            }
+        Ok(Ok(EcResult::Profile(state))) => {
+            let name = {
+                let config_guard = config.lock().unwrap();
+                config_guard.profiles.iter()
+                    .find(|(_, profile)| profile.matches(&state))
+                    .map(|(name, _)| name.clone())
+            };
             
-            // Save to config
-            {
-                let mut config_guard = config.lock().unwrap();
-                let fan_config_opt = match fan_id {
-                    1 => &mut config_guard.fan1,
-                    2 => &mut config_guard.fan2,
-                    3 => &mut config_guard.fan3,
-                    _ => return Ok(warp::reply::with_status(
-                        warp::reply::json(&ErrorResponse { error: "Invalid fan ID".to_string() }),
-                        warp::http::StatusCode::BAD_REQUEST,
-                    )),
-                };
-                
-                // Create fan config if it doesn't exist
-                if fan_config_opt.is_none() {
-                    *fan_config_opt = Some(config::FanConfig::default());
-                }
-                
-                if let Some(fan_config) = fan_config_opt {
-                    fan_config.rampup_curve = curve;
-                    if let Err(e) = config_guard.save() {
-                        let mut log = logger.lock().unwrap();
-                        log.warn(&format!("Failed to save Fan{} rampup curve to config: {}", fan_id, e));
-                    }
-                }
-            }
+            log::debug!("Active profile get: {:?}", name);
             
             Ok(warp::reply::with_status(
-                warp::reply::json(&FanCurveResponse { curve }),
+                warp::reply::json(&ActiveProfileResponse { name }),
                 warp::http::StatusCode::OK,
             ))
         }
         Ok(Err(e)) => Ok(warp::reply::with_status(
             warp::reply::json(&ErrorResponse { error: e }),
-            warp::http::StatusCode::BAD_REQUEST,
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
         )),
         Ok(Ok(_)) => Ok(warp::reply::with_status(
             warp::reply::json(&ErrorResponse {
@@ -1531,249 +4015,646 @@ async fn handle_fan_rampup_curve_post(
     }
 }
 
-async fn handle_fan_rampdown_curve_get(
-    fan_id: u8,
-    logger: Arc<Mutex<Logger>>,
-    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
-) -> std::result::Result<impl warp::Reply, warp::Rejection> {
-    let (tx, rx) = tokio::sync::oneshot::channel();
-    
-    if ec_queue.send((EcOperation::GetFanRampdownCurve(fan_id), tx)).is_err() {
-        return Ok(warp::reply::with_status(
-            warp::reply::json(&ErrorResponse {
-                error: "EC queue unavailable".to_string(),
-            }),
-            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
-        ));
+fn schedules_response(schedules: Vec<schedules::Schedule>) -> SchedulesResponse {
+    let now = chrono::Local::now().naive_local();
+    SchedulesResponse {
+        active: schedules::active(&schedules, now).map(|(index, _)| index),
+        next_boundary: schedules::next_boundary(&schedules, now),
+        schedules,
     }
+}
 
-    match rx.await {
-        Ok(Ok(EcResult::FanRampdownCurve(curve))) => {
-            {
-                let mut log = logger.lock().unwrap();
-                log.info(&format!("Fan{} rampdown curve get: {:?}", fan_id, curve));
+async fn handle_schedules_get(
+    config: Arc<Mutex<ServerConfig>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let schedules = {
+        let config_guard = config.lock().unwrap();
+        config_guard.schedules.clone()
+    };
+
+    let response = schedules_response(schedules);
+
+    log::debug!("Schedules get: {} schedules, active: {:?}", response.schedules.len(), response.active);
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&response),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+async fn handle_schedules_post(
+    request: Vec<schedules::Schedule>,
+    config: Arc<Mutex<ServerConfig>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    {
+        let mut config_guard = config.lock().unwrap();
+        
+        for (index, schedule) in request.iter().enumerate() {
+            let result = schedule.validate().and_then(|_| config_guard.check_profile(&schedule.profile));
+            
+            if let Err(e) = result {
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&ErrorResponse { error: format!("Schedule {}: {}", index, e) }),
+                    warp::http::StatusCode::BAD_REQUEST,
+                ));
             }
+        }
+        
+        log::info!("Schedules set to: {:?}", request);
+        
+        // Save to config, the schedule task picks the new schedules up on its next tick
+        config_guard.schedules = request.clone();
+        if let Err(e) = config_guard.save() {
+            log::warn!("Failed to save schedules to config: {}", e);
+        }
+    }
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&schedules_response(request)),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+async fn handle_schedule_override_get(
+    schedule_override: Arc<Mutex<Option<schedules::ScheduleOverride>>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let schedule_override = schedule_override.lock().unwrap().clone();
+
+    log::debug!("Schedule override get: {:?}", schedule_override);
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&schedule_override),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+async fn handle_schedule_override_post(
+    request: ScheduleOverrideRequest,
+    config: Arc<Mutex<ServerConfig>>,
+    schedule_override: Arc<Mutex<Option<schedules::ScheduleOverride>>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let new_override = {
+        let config_guard = config.lock().unwrap();
+        if let Err(e) = config_guard.check_profile(&request.profile) {
+            return Ok(warp::reply::with_status(
+                warp::reply::json(&ErrorResponse { error: e }),
+                warp::http::StatusCode::BAD_REQUEST,
+            ));
+        }
+        
+        // Not saved to config, a restart goes back to the schedules. The automation task applies the
+        // profile on its next tick, unless a process rule is in charge.
+        let new_override = schedules::ScheduleOverride::new(request.profile, &config_guard.schedules, chrono::Local::now().naive_local());
+        *schedule_override.lock().unwrap() = Some(new_override.clone());
+        new_override
+    };
+
+    log::info!("Schedule override set to: {:?}", new_override);
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&new_override),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+async fn handle_schedule_override_delete(
+    schedule_override: Arc<Mutex<Option<schedules::ScheduleOverride>>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let removed = match schedule_override.lock().unwrap().take() {
+        Some(removed) => removed,
+        None => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: "No schedule override".to_string() }),
+            warp::http::StatusCode::NOT_FOUND,
+        )),
+    };
+
+    log::info!("Schedule override of profile {} removed", removed.profile);
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&removed),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+fn process_rules_response(rules: Vec<process_rules::ProcessRule>, watcher: &process_rules::ProcessWatcher) -> ProcessRulesResponse {
+    // The watcher catches up with changed rules on its next tick, until then nothing matches
+    let (matched, active) = if watcher.rules() == rules.as_slice() {
+        (watcher.matched(), watcher.active().map(|(index, _)| index))
+    } else {
+        (vec![false; rules.len()], None)
+    };
+
+    ProcessRulesResponse { rules, matched, active }
+}
+
+async fn handle_process_rules_get(
+    config: Arc<Mutex<ServerConfig>>,
+    process_watcher: Arc<Mutex<process_rules::ProcessWatcher>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let rules = {
+        let config_guard = config.lock().unwrap();
+        config_guard.process_rules.clone()
+    };
+
+    let response = {
+        let watcher = process_watcher.lock().unwrap();
+        process_rules_response(rules, &watcher)
+    };
+
+    log::debug!("Process rules get: {} rules, active: {:?}", response.rules.len(), response.active);
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&response),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+async fn handle_process_rules_post(
+    request: Vec<process_rules::ProcessRule>,
+    config: Arc<Mutex<ServerConfig>>,
+    process_watcher: Arc<Mutex<process_rules::ProcessWatcher>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    {
+        let mut config_guard = config.lock().unwrap();
+        
+        for (index, rule) in request.iter().enumerate() {
+            let result = rule.validate().and_then(|_| config_guard.check_profile(&rule.profile));
             
-            Ok(warp::reply::with_status(
-                warp::reply::json(&FanCurveResponse { curve }),
-                warp::http::StatusCode::OK,
-            ))
+            if let Err(e) = result {
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&ErrorResponse { error: format!("Rule {}: {}", index, e) }),
+                    warp::http::StatusCode::BAD_REQUEST,
+                ));
+            }
         }
-        Ok(Err(e)) => Ok(warp::reply::with_status(
-            warp::reply::json(&ErrorResponse { error: e }),
-            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        
+        log::info!("Process rules set to: {:?}", request);
+        
+        // Save to config, the automation task picks the new rules up on its next tick
+        config_guard.process_rules = request.clone();
+        if let Err(e) = config_guard.save() {
+            log::warn!("Failed to save process rules to config: {}", e);
+        }
+    }
+
+    let response = {
+        let watcher = process_watcher.lock().unwrap();
+        process_rules_response(request, &watcher)
+    };
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&response),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+// Validates the duration of a timed override or pending change and reads the value it replaces
+async fn prepare_override(
+    ec_queue: &Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
+    setting: &overrides::Setting,
+    duration_seconds: Option<u64>,
+    confirm_timeout_seconds: Option<u64>,
+) -> Result<Option<overrides::Temporary>, String> {
+    let (seconds, pending) = match overrides::temporary_seconds(duration_seconds, confirm_timeout_seconds)? {
+        Some(temporary) => temporary,
+        None => return Ok(None),
+    };
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    if ec_queue.send((setting.read_operation(), tx)).is_err() {
+        return Err("EC queue unavailable".to_string());
+    }
+
+    let previous = match (setting, rx.await) {
+        (overrides::Setting::PowerMode { .. }, Ok(Ok(EcResult::ApuPowerMode(power_mode)))) => {
+            Some(overrides::Setting::PowerMode { power_mode })
+        }
+        (overrides::Setting::FanMode { fan, .. }, Ok(Ok(EcResult::FanMode(mode)))) => {
+            Some(overrides::Setting::FanMode { fan: *fan, mode })
+        }
+        (overrides::Setting::FanLevel { fan, .. }, Ok(Ok(EcResult::FanLevel(level)))) => {
+            Some(overrides::Setting::FanLevel { fan: *fan, level })
+        }
+        (overrides::Setting::RampupCurve { fan, .. }, Ok(Ok(EcResult::FanRampupCurve(curve)))) => {
+            Some(overrides::Setting::RampupCurve { fan: *fan, curve })
+        }
+        (overrides::Setting::RampdownCurve { fan, .. }, Ok(Ok(EcResult::FanRampdownCurve(curve)))) => {
+            Some(overrides::Setting::RampdownCurve { fan: *fan, curve })
+        }
+        _ => None,
+    };
+
+    Ok(Some(overrides::Temporary { seconds, pending, previous }))
+}
+
+fn start_override(
+    overrides: &Arc<Mutex<overrides::Overrides>>,
+    setting: overrides::Setting,
+    temporary: overrides::Temporary,
+) -> overrides::Override {
+    let seconds = temporary.seconds;
+    let entry = overrides.lock().unwrap().add(setting, temporary, chrono::Utc::now());
+
+    if entry.pending {
+        log::info!("Change {} pending, reverted unless confirmed within {} seconds: {:?}", entry.id, seconds, entry.setting);
+    } else {
+        log::info!("Override {} started for {} seconds: {:?}", entry.id, seconds, entry.setting);
+    }
+
+    entry
+}
+
+async fn revert_override(
+    entry: &overrides::Override,
+    config: &Arc<Mutex<ServerConfig>>,
+    ec_queue: &Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
+) -> Result<overrides::Setting, String> {
+    let setting = {
+        let config_guard = config.lock().unwrap();
+        entry.revert_setting(&config_guard)
+    };
+    let setting = setting.ok_or_else(|| "No saved or previous value to revert to".to_string())?;
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    if ec_queue.send((setting.operation(), tx)).is_err() {
+        return Err("EC queue unavailable".to_string());
+    }
+
+    match rx.await {
+        Ok(Ok(_)) => {
+            let kind = if entry.pending { "Pending change" } else { "Override" };
+            log::info!("{} {} reverted to: {:?}", kind, entry.id, setting);
+            Ok(setting)
+        }
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err("Communication timeout".to_string()),
+    }
+}
+
+fn override_response(entry: &overrides::Override, now: chrono::DateTime<chrono::Utc>) -> OverrideResponse {
+    OverrideResponse {
+        id: entry.id,
+        setting: entry.setting.clone(),
+        pending: entry.pending,
+        created: entry.created,
+        expires: entry.expires,
+        remaining_seconds: (entry.expires - now).num_seconds().max(0) as u64,
+    }
+}
+
+async fn handle_overrides_get(
+    overrides: Arc<Mutex<overrides::Overrides>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let now = chrono::Utc::now();
+    let overrides: Vec<OverrideResponse> = {
+        let overrides_guard = overrides.lock().unwrap();
+        overrides_guard.list().iter().map(|entry| override_response(entry, now)).collect()
+    };
+
+    log::debug!("Overrides get: {} active", overrides.len());
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&OverridesResponse { overrides }),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+async fn handle_drift_get(
+    drift_log: Arc<Mutex<reconcile::DriftLog>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let events = drift_log.lock().unwrap().events();
+
+    log::debug!("Drift events get: {} recorded", events.len());
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&DriftResponse { events }),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+async fn handle_override_delete(
+    id: u64,
+    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
+    config: Arc<Mutex<ServerConfig>>,
+    overrides: Arc<Mutex<overrides::Overrides>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let entry = overrides.lock().unwrap().remove(id);
+    let entry = match entry {
+        Some(entry) => entry,
+        None => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: format!("Unknown override: {}", id) }),
+            warp::http::StatusCode::NOT_FOUND,
         )),
-        Ok(Ok(_)) => Ok(warp::reply::with_status(
-            warp::reply::json(&ErrorResponse {
-                error: "Unexpected response type".to_string(),
-            }),
-            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    log::info!("Override {} cancelled", id);
+
+    match revert_override(&entry, &config, &ec_queue).await {
+        Ok(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&override_response(&entry, chrono::Utc::now())),
+            warp::http::StatusCode::OK,
         )),
-        Err(_) => Ok(warp::reply::with_status(
-            warp::reply::json(&ErrorResponse {
-                error: "Communication timeout".to_string(),
-            }),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: format!("Failed to revert override {}: {}", id, e) }),
             warp::http::StatusCode::INTERNAL_SERVER_ERROR,
         )),
     }
 }
 
-async fn handle_fan_rampdown_curve_post(
-    request: FanCurveRequest,
-    fan_id: u8,
-    logger: Arc<Mutex<Logger>>,
-    ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<std::result::Result<EcResult, String>>)>>,
+async fn handle_change_confirm(
+    id: u64,
     config: Arc<Mutex<ServerConfig>>,
-) -> std::result::Result<impl warp::Reply, warp::Rejection> {
-    let (tx, rx) = tokio::sync::oneshot::channel();
-    
-    if ec_queue.send((EcOperation::SetFanRampdownCurve(fan_id, request.curve), tx)).is_err() {
+    overrides: Arc<Mutex<overrides::Overrides>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let entry = overrides.lock().unwrap().confirm(id);
+    let entry = match entry {
+        Some(entry) => entry,
+        None => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: format!("Unknown pending change: {}", id) }),
+            warp::http::StatusCode::NOT_FOUND,
+        )),
+    };
+
+    log::info!("Change {} confirmed: {:?}", id, entry.setting);
+
+    // Save to config, the change is permanent now
+    {
+        let mut config_guard = config.lock().unwrap();
+        entry.setting.persist(&mut config_guard);
+        if let Err(e) = config_guard.save() {
+            log::warn!("Failed to save confirmed change {} to config: {}", id, e);
+        }
+    }
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&override_response(&entry, chrono::Utc::now())),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+async fn handle_logging_level_get(
+    config: Arc<Mutex<ServerConfig>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let levels = config.lock().unwrap().log_levels.clone();
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&levels),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+async fn handle_logging_level_put(
+    request: logger::LogLevels,
+    config: Arc<Mutex<ServerConfig>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    // Takes effect right away, an invalid level leaves the current ones as they are
+    if let Err(e) = logger::set_levels(&request) {
         return Ok(warp::reply::with_status(
-            warp::reply::json(&ErrorResponse {
-                error: "EC queue unavailable".to_string(),
-            }),
-            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::BAD_REQUEST,
         ));
     }
 
-    match rx.await {
-        Ok(Ok(EcResult::FanRampdownCurve(curve))) => {
-            {
-                let mut log = logger.lock().unwrap();
-                log.info(&format!("Fan{} rampdown curve set to: {:?}", fan_id, curve));
-            }
-            
-            // Save to config
-            {
-                let mut config_guard = config.lock().unwrap();
-                let fan_config_opt = match fan_id {
-                    1 => &mut config_guard.fan1,
-                    2 => &mut config_guard.fan2,
-                    3 => &mut config_guard.fan3,
-                    _ => return Ok(warp::reply::with_status(
-                        warp::reply::json(&ErrorResponse { error: "Invalid fan ID".to_string() }),
-                        warp::http::StatusCode::BAD_REQUEST,
-                    )),
-                };
-                
-                // Create fan config if it doesn't exist
-                if fan_config_opt.is_none() {
-                    *fan_config_opt = Some(config::FanConfig::default());
-                }
-                
-                if let Some(fan_config) = fan_config_opt {
-                    fan_config.rampdown_curve = curve;
-                    if let Err(e) = config_guard.save() {
-                        let mut log = logger.lock().unwrap();
-                        log.warn(&format!("Failed to save Fan{} rampdown curve to config: {}", fan_id, e));
-                    }
-                }
-            }
-            
-            Ok(warp::reply::with_status(
-                warp::reply::json(&FanCurveResponse { curve }),
-                warp::http::StatusCode::OK,
-            ))
+    log::info!("Log levels set to: {:?}", request);
+
+    {
+        let mut config_guard = config.lock().unwrap();
+        config_guard.log_levels = request.clone();
+        if let Err(e) = config_guard.save() {
+            log::warn!("Failed to save log levels to config: {}", e);
         }
-        Ok(Err(e)) => Ok(warp::reply::with_status(
-            warp::reply::json(&ErrorResponse { error: e }),
-            warp::http::StatusCode::BAD_REQUEST,
-        )),
-        Ok(Ok(_)) => Ok(warp::reply::with_status(
-            warp::reply::json(&ErrorResponse {
-                error: "Unexpected response type".to_string(),
-            }),
-            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+    }
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&request),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+async fn handle_schemas_get() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::with_status(
+        warp::reply::json(&SchemasResponse {
+            schemas: schema::SCHEMA_NAMES.iter().map(|name| name.to_string()).collect(),
+        }),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+async fn handle_schema_get(name: String) -> Result<impl warp::Reply, warp::Rejection> {
+    match schema::schema(&name) {
+        Some(schema) => Ok(warp::reply::with_status(
+            warp::reply::json(&schema),
+            warp::http::StatusCode::OK,
         )),
-        Err(_) => Ok(warp::reply::with_status(
-            warp::reply::json(&ErrorResponse {
-                error: "Communication timeout".to_string(),
-            }),
-            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        None => Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: format!("Unknown schema: {}", name) }),
+            warp::http::StatusCode::NOT_FOUND,
         )),
     }
 }
 
-async fn handle_fan_level_get(
-    fan_id: u8,
-    logger: Arc<Mutex<Logger>>,
+async fn handle_config_get(
+    query: ConfigQuery,
+    config: Arc<Mutex<ServerConfig>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut value = serde_json::to_value(&*config.lock().unwrap()).unwrap_or_default();
+    if query.redact_paths {
+        if let Some(map) = value.as_object_mut() {
+            map.remove("log_path");
+            map.remove("driver_path");
+        }
+    }
+
+    log::info!("Config exported");
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&value),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+async fn handle_config_put(
+    query: ConfigQuery,
+    mut request: serde_json::Value,
+    config: Arc<Mutex<ServerConfig>>,
     ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
+    rebind: Arc<tokio::sync::watch::Sender<()>>,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    let (tx, rx) = tokio::sync::oneshot::channel();
-    
-    if ec_queue.send((EcOperation::GetFanLevel(fan_id), tx)).is_err() {
-        return Ok(warp::reply::with_status(
-            warp::reply::json(&ErrorResponse {
-                error: "EC queue unavailable".to_string(),
-            }),
-            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
-        ));
+    // The paths of this machine are always kept: the service loads its driver from driver_path and writes
+    // log_path as SYSTEM, so neither may be changed over HTTP
+    if let Some(map) = request.as_object_mut() {
+        let config_guard = config.lock().unwrap();
+        map.insert("log_path".to_string(), config_guard.log_path.clone().into());
+        map.insert("driver_path".to_string(), config_guard.driver_path.clone().into());
     }
 
-    match rx.await {
-        Ok(Ok(EcResult::FanLevel(level))) => {
-            {
-                let mut log = logger.lock().unwrap();
-                log.info(&format!("Fan{} level: {}", fan_id, level));
-            }
-            
-            Ok(warp::reply::with_status(
-                warp::reply::json(&FanLevelResponse { level }),
-                warp::http::StatusCode::OK,
-            ))
-        }
-        Ok(Err(e)) => Ok(warp::reply::with_status(
+    // Nothing is touched unless the whole config is valid
+    let mut new_config = match ServerConfig::from_json(request)
+        .and_then(|new_config| new_config.validate_import().map(|_| new_config)) {
+        Ok(new_config) => new_config,
+        Err(e) => return Ok(warp::reply::with_status(
             warp::reply::json(&ErrorResponse { error: e }),
-            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
-        )),
-        Ok(Ok(_)) => Ok(warp::reply::with_status(
-            warp::reply::json(&ErrorResponse {
-                error: "Unexpected response type".to_string(),
-            }),
-            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            warp::http::StatusCode::BAD_REQUEST,
         )),
-        Err(_) => Ok(warp::reply::with_status(
-            warp::reply::json(&ErrorResponse {
-                error: "Communication timeout".to_string(),
-            }),
+    };
+    {
+        // Overridden and relative paths keep the value the file has for them
+        let config_guard = config.lock().unwrap();
+        for setting in ["log_path", "driver_path"] {
+            match config_guard.overridden.get(setting) {
+                Some(file_value) => new_config.overridden.insert(setting.to_string(), file_value.clone()),
+                None => new_config.overridden.remove(setting),
+            };
+        }
+        new_config.unresolved_paths = config_guard.unresolved_paths.clone();
+    }
+
+    let (changes, ec_writes) = {
+        let config_guard = config.lock().unwrap();
+        let ec_writes = reload::ec_operations(&config_guard, &new_config).into_iter()
+            .map(|(setting, _)| setting)
+            .collect::<Vec<_>>();
+        (reload::diff(&config_guard, &new_config), ec_writes)
+    };
+
+    if query.dry_run {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ConfigImportResponse { dry_run: true, changes, ec_writes }),
+            warp::http::StatusCode::OK,
+        ));
+    }
+
+    // Nothing is saved unless every EC write worked, the EC is set back to the running config otherwise
+    let status = apply_config(new_config, &config, &ec_queue, &rebind, "Config imported", true).await;
+    if let Some(error) = status.error {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error }),
             warp::http::StatusCode::INTERNAL_SERVER_ERROR,
-        )),
+        ));
     }
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&ConfigImportResponse { dry_run: false, changes, ec_writes }),
+        warp::http::StatusCode::OK,
+    ))
 }
 
-async fn handle_fan_level_post(
-    request: FanLevelRequest,
+async fn handle_fan_curve_simulate(
     fan_id: u8,
-    logger: Arc<Mutex<Logger>>,
+    request: FanCurveSimulationRequest,
     ec_queue: Arc<mpsc::UnboundedSender<(EcOperation, tokio::sync::oneshot::Sender<Result<EcResult, String>>)>>,
-    config: Arc<Mutex<ServerConfig>>,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    let (tx, rx) = tokio::sync::oneshot::channel();
-    
-    if ec_queue.send((EcOperation::SetFanLevel(fan_id, request.level), tx)).is_err() {
+    if !(1..=3).contains(&fan_id) {
         return Ok(warp::reply::with_status(
-            warp::reply::json(&ErrorResponse {
-                error: "EC queue unavailable".to_string(),
-            }),
-            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            warp::reply::json(&ErrorResponse { error: format!("Invalid fan ID: {}", fan_id) }),
+            warp::http::StatusCode::BAD_REQUEST,
         ));
     }
 
-    match rx.await {
-        Ok(Ok(EcResult::FanLevel(level))) => {
-            {
-                let mut log = logger.lock().unwrap();
-                log.info(&format!("Fan{} level set to: {}", fan_id, level));
-            }
-            
-            // Save to config
-            {
-                let mut config_guard = config.lock().unwrap();
-                let fan_config_opt = match fan_id {
-                    1 => &mut config_guard.fan1,
-                    2 => &mut config_guard.fan2,
-                    3 => &mut config_guard.fan3,
-                    _ => return Ok(warp::reply::with_status(
-                        warp::reply::json(&ErrorResponse { error: "Invalid fan ID".to_string() }),
+    // Helper function to execute EC operation
+    let execute_operation = |operation: EcOperation| async {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        if ec_queue.send((operation, tx)).is_err() {
+            return Err("EC queue unavailable".to_string());
+        }
+        match rx.await {
+            Ok(result) => result,
+            Err(_) => Err("Communication timeout".to_string()),
+        }
+    };
+
+    // Fall back to the curves the engine currently uses for anything not provided
+    let (rampup_curve, rampdown_curve) = match (request.rampup_curve, request.rampdown_curve) {
+        (Some(rampup_curve), Some(rampdown_curve)) => (rampup_curve, rampdown_curve),
+        (rampup_curve, rampdown_curve) => match execute_operation(EcOperation::GetFanActiveCurves(fan_id)).await {
+            Ok(EcResult::FanActiveCurves { curves, .. }) => (
+                rampup_curve.unwrap_or(curves.rampup_curve),
+                rampdown_curve.unwrap_or(curves.rampdown_curve),
+            ),
+            _ => return Ok(warp::reply::with_status(
+                warp::reply::json(&ErrorResponse { error: format!("Failed to get Fan{} active curves", fan_id) }),
+                warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        },
+    };
+
+    if let Err(e) = curve::validate_curve(&rampup_curve).and_then(|_| curve::validate_curve(&rampdown_curve)) {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: e }),
+            warp::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    // Use the provided temperature series or the recorded history
+    let samples: Vec<(u8, u64)> = match request.temperatures {
+        Some(temperatures) => {
+            let interval = request.interval_seconds.unwrap_or(curve::CURVE_TICK_SECONDS);
+            temperatures.into_iter().map(|temp| (temp, interval)).collect()
+        }
+        None => match execute_operation(EcOperation::GetTemperatureHistory(fan_id)).await {
+            Ok(EcResult::TemperatureHistory(history)) => {
+                if history.is_empty() {
+                    return Ok(warp::reply::with_status(
+                        warp::reply::json(&ErrorResponse { error: format!("No recorded temperature history available for Fan{}", fan_id) }),
                         warp::http::StatusCode::BAD_REQUEST,
-                    )),
-                };
-                
-                // Create fan config if it doesn't exist
-                if fan_config_opt.is_none() {
-                    *fan_config_opt = Some(config::FanConfig::default());
+                    ));
                 }
-                
-                if let Some(fan_config) = fan_config_opt {
-                    fan_config.level = level;
-                    if let Err(e) = config_guard.save() {
-                        let mut log = logger.lock().unwrap();
-                        log.warn(&format!("Failed to save Fan{} level to config: {}", fan_id, e));
-                    }
+
+                // Each sample lasts until the next one was recorded
+                let mut samples = Vec::with_capacity(history.len());
+                for (i, (timestamp, temp)) in history.iter().enumerate() {
+                    let duration = match history.get(i + 1) {
+                        Some((next_timestamp, _)) => (*next_timestamp - *timestamp).num_seconds().max(0) as u64,
+                        None => curve::CURVE_TICK_SECONDS,
+                    };
+                    samples.push((*temp, duration));
                 }
+                samples
             }
-            
-            
+            _ => return Ok(warp::reply::with_status(
+                warp::reply::json(&ErrorResponse { error: "Failed to get temperature history".to_string() }),
+                warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        },
+    };
+
+    // Steps are held to the limits like the curve engine does
+    let limits = match execute_operation(EcOperation::GetFanLimits(fan_id)).await {
+        Ok(EcResult::FanLimits(limits)) => limits,
+        _ => return Ok(warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error: format!("Failed to get Fan{} limits", fan_id) }),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    };
+
+    match curve::simulate(&samples, request.initial_level, &rampup_curve, &rampdown_curve, &limits) {
+        Ok(simulation) => {
+            log::info!("Fan{} curve simulation: {} samples, {} transitions", fan_id, samples.len(), simulation.transitions);
+
+            let steps = samples.iter()
+                .zip(simulation.levels.iter().zip(simulation.clamps))
+                .map(|(&(temperature, duration_seconds), (&level, clamped))| FanCurveSimulationStep {
+                    temperature,
+                    duration_seconds,
+                    level,
+                    clamped,
+                })
+                .collect();
+
             Ok(warp::reply::with_status(
-                warp::reply::json(&FanLevelResponse { level }),
+                warp::reply::json(&FanCurveSimulationResponse {
+                    rampup_curve,
+                    rampdown_curve,
+                    steps,
+                    transitions: simulation.transitions,
+                    time_at_level: simulation.time_at_level,
+                }),
                 warp::http::StatusCode::OK,
             ))
         }
-        Ok(Err(e)) => Ok(warp::reply::with_status(
+        Err(e) => Ok(warp::reply::with_status(
             warp::reply::json(&ErrorResponse { error: e }),
             warp::http::StatusCode::BAD_REQUEST,
         )),
-        Ok(Ok(_)) => Ok(warp::reply::with_status(
-            warp::reply::json(&ErrorResponse {
-                error: "Unexpected response type".to_string(),
-            }),
-            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
-        )),
-        Err(_) => Ok(warp::reply::with_status(
-            warp::reply::json(&ErrorResponse {
-                error: "Communication timeout".to_string(),
-            }),
-            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
-        )),
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,50 @@
+// Upgrades of config files written by older versions, applied one version at a time on the raw JSON
+// before it is parsed into ServerConfig
+
+use serde_json::{Map, Value};
+
+// Version written by this build, bump it together with a new entry in MIGRATIONS
+pub const CURRENT_CONFIG_VERSION: u32 = 1;
+
+type Migration = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+// MIGRATIONS[n] upgrades a version n config to version n + 1
+const MIGRATIONS: [Migration; CURRENT_CONFIG_VERSION as usize] = [
+    migrate_v0_to_v1,
+];
+
+// Version 0 files were written before config_version existed, their layout is the same as version 1
+fn migrate_v0_to_v1(_config: &mut Map<String, Value>) -> Result<(), String> {
+    Ok(())
+}
+
+pub fn config_version(config: &Value) -> Result<u32, String> {
+    match config.get("config_version") {
+        None => Ok(0),
+        Some(version) => version.as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| format!("Invalid config_version: {}", version)),
+    }
+}
+
+// Returns the version the config had if it was upgraded
+pub fn migrate(config: &mut Value) -> Result<Option<u32>, String> {
+    let original = config_version(config)?;
+    if original > CURRENT_CONFIG_VERSION {
+        return Err(format!("Config version {} is newer than the supported version {}, was it written by a newer server?",
+            original, CURRENT_CONFIG_VERSION));
+    }
+    if original == CURRENT_CONFIG_VERSION {
+        return Ok(None);
+    }
+
+    let map = config.as_object_mut()
+        .ok_or_else(|| "Config must be a JSON object".to_string())?;
+    for version in original..CURRENT_CONFIG_VERSION {
+        MIGRATIONS[version as usize](map)
+            .map_err(|e| format!("Failed to migrate config from version {} to {}: {}", version, version + 1, e))?;
+        map.insert("config_version".to_string(), Value::from(version + 1));
+    }
+
+    Ok(Some(original))
+}
@@ -0,0 +1,212 @@
+// Temporary settings that are reverted to the persisted state when their timer runs out: timed overrides,
+// and pending changes that become permanent only if they are confirmed in time
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{FanConfig, ServerConfig};
+use crate::ec::EcOperation;
+
+// Longest duration an override may have (one day)
+pub const MAX_OVERRIDE_SECONDS: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "parameter", rename_all = "snake_case")]
+pub enum Setting {
+    PowerMode { power_mode: String },
+    FanMode { fan: u8, mode: String },
+    FanLevel { fan: u8, level: u8 },
+    RampupCurve { fan: u8, curve: [u8; 5] },
+    RampdownCurve { fan: u8, curve: [u8; 5] },
+}
+
+impl Setting {
+    pub fn operation(&self) -> EcOperation {
+        match self {
+            Setting::PowerMode { power_mode } => EcOperation::SetApuPowerMode(power_mode.clone()),
+            Setting::FanMode { fan, mode } => EcOperation::SetFanMode(*fan, mode.clone()),
+            Setting::FanLevel { fan, level } => EcOperation::SetFanLevel(*fan, *level),
+            Setting::RampupCurve { fan, curve } => EcOperation::SetFanRampupCurve(*fan, *curve),
+            Setting::RampdownCurve { fan, curve } => EcOperation::SetFanRampdownCurve(*fan, *curve),
+        }
+    }
+
+    // Operation reading the current value of the same parameter
+    pub fn read_operation(&self) -> EcOperation {
+        match self {
+            Setting::PowerMode { .. } => EcOperation::GetApuPowerMode,
+            Setting::FanMode { fan, .. } => EcOperation::GetFanMode(*fan),
+            Setting::FanLevel { fan, .. } => EcOperation::GetFanLevel(*fan),
+            Setting::RampupCurve { fan, .. } => EcOperation::GetFanRampupCurve(*fan),
+            Setting::RampdownCurve { fan, .. } => EcOperation::GetFanRampdownCurve(*fan),
+        }
+    }
+
+    pub fn same_parameter(&self, other: &Setting) -> bool {
+        match (self, other) {
+            (Setting::PowerMode { .. }, Setting::PowerMode { .. }) => true,
+            (Setting::FanMode { fan: a, .. }, Setting::FanMode { fan: b, .. })
+            | (Setting::FanLevel { fan: a, .. }, Setting::FanLevel { fan: b, .. })
+            | (Setting::RampupCurve { fan: a, .. }, Setting::RampupCurve { fan: b, .. })
+            | (Setting::RampdownCurve { fan: a, .. }, Setting::RampdownCurve { fan: b, .. }) => a == b,
+            _ => false,
+        }
+    }
+
+    pub fn persist(&self, config: &mut ServerConfig) {
+        match self {
+            Setting::PowerMode { power_mode } => config.apu_power_mode = Some(power_mode.clone()),
+            Setting::FanMode { fan, mode } => {
+                if let Some(fan_config) = fan_config_mut(config, *fan) {
+                    fan_config.mode = mode.clone();
+                }
+            }
+            Setting::FanLevel { fan, level } => {
+                if let Some(fan_config) = fan_config_mut(config, *fan) {
+                    fan_config.level = *level;
+                }
+            }
+            Setting::RampupCurve { fan, curve } => {
+                if let Some(fan_config) = fan_config_mut(config, *fan) {
+                    fan_config.rampup_curve = *curve;
+                }
+            }
+            Setting::RampdownCurve { fan, curve } => {
+                if let Some(fan_config) = fan_config_mut(config, *fan) {
+                    fan_config.rampdown_curve = *curve;
+                }
+            }
+        }
+    }
+
+    // Value of the same parameter saved in config, if any
+    pub fn persisted(&self, config: &ServerConfig) -> Option<Setting> {
+        let fan_config = |fan: u8| -> Option<&FanConfig> {
+            match fan {
+                1 => config.fan1.as_ref(),
+                2 => config.fan2.as_ref(),
+                3 => config.fan3.as_ref(),
+                _ => None,
+            }
+        };
+
+        match self {
+            Setting::PowerMode { .. } => config.apu_power_mode.clone()
+                .map(|power_mode| Setting::PowerMode { power_mode }),
+            Setting::FanMode { fan, .. } => fan_config(*fan)
+                .map(|c| Setting::FanMode { fan: *fan, mode: c.mode.clone() }),
+            Setting::FanLevel { fan, .. } => fan_config(*fan)
+                .map(|c| Setting::FanLevel { fan: *fan, level: c.level }),
+            Setting::RampupCurve { fan, .. } => fan_config(*fan)
+                .map(|c| Setting::RampupCurve { fan: *fan, curve: c.rampup_curve }),
+            Setting::RampdownCurve { fan, .. } => fan_config(*fan)
+                .map(|c| Setting::RampdownCurve { fan: *fan, curve: c.rampdown_curve }),
+        }
+    }
+}
+
+// Creates the fan config if it doesn't exist
+fn fan_config_mut(config: &mut ServerConfig, fan: u8) -> Option<&mut FanConfig> {
+    let fan_config = match fan {
+        1 => &mut config.fan1,
+        2 => &mut config.fan2,
+        3 => &mut config.fan3,
+        _ => return None,
+    };
+    Some(fan_config.get_or_insert_with(FanConfig::default))
+}
+
+// How a change is applied if the request asks for it to be temporary
+#[derive(Debug, Clone)]
+pub struct Temporary {
+    pub seconds: u64,
+    pub pending: bool,
+    pub previous: Option<Setting>,
+}
+
+pub fn temporary_seconds(duration_seconds: Option<u64>, confirm_timeout_seconds: Option<u64>) -> Result<Option<(u64, bool)>, String> {
+    let (seconds, pending) = match (duration_seconds, confirm_timeout_seconds) {
+        (None, None) => return Ok(None),
+        (Some(_), Some(_)) => return Err("duration_seconds and confirm_timeout_seconds can't be combined".to_string()),
+        (Some(seconds), None) => (seconds, false),
+        (None, Some(seconds)) => (seconds, true),
+    };
+
+    if seconds == 0 || seconds > MAX_OVERRIDE_SECONDS {
+        return Err(format!("Duration must be 1-{} seconds", MAX_OVERRIDE_SECONDS));
+    }
+    Ok(Some((seconds, pending)))
+}
+
+#[derive(Debug, Clone)]
+pub struct Override {
+    pub id: u64,
+    pub setting: Setting,
+    pub previous: Option<Setting>,  // Value before the override, used if config has none
+    pub pending: bool,              // Pending change waiting for confirmation instead of a timed override
+    pub created: DateTime<Utc>,
+    pub expires: DateTime<Utc>,
+}
+
+impl Override {
+    pub fn override_id(&self) -> Option<u64> {
+        (!self.pending).then_some(self.id)
+    }
+
+    pub fn change_id(&self) -> Option<u64> {
+        self.pending.then_some(self.id)
+    }
+
+    pub fn revert_setting(&self, config: &ServerConfig) -> Option<Setting> {
+        self.setting.persisted(config).or_else(|| self.previous.clone())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Overrides {
+    next_id: u64,
+    active: Vec<Override>,
+}
+
+impl Overrides {
+    // A new override replaces one for the same parameter, but keeps the value from before the first one
+    pub fn add(&mut self, setting: Setting, temporary: Temporary, now: DateTime<Utc>) -> Override {
+        let mut previous = temporary.previous;
+        if let Some(index) = self.active.iter().position(|o| o.setting.same_parameter(&setting)) {
+            previous = self.active.remove(index).previous;
+        }
+
+        self.next_id += 1;
+        let entry = Override {
+            id: self.next_id,
+            setting,
+            previous,
+            pending: temporary.pending,
+            created: now,
+            expires: now + Duration::seconds(temporary.seconds as i64),
+        };
+        self.active.push(entry.clone());
+        entry
+    }
+
+    pub fn remove(&mut self, id: u64) -> Option<Override> {
+        let index = self.active.iter().position(|o| o.id == id)?;
+        Some(self.active.remove(index))
+    }
+
+    // Only pending changes can be confirmed
+    pub fn confirm(&mut self, id: u64) -> Option<Override> {
+        let index = self.active.iter().position(|o| o.id == id && o.pending)?;
+        Some(self.active.remove(index))
+    }
+
+    pub fn take_expired(&mut self, now: DateTime<Utc>) -> Vec<Override> {
+        let (expired, active) = self.active.drain(..).partition(|o| o.expires <= now);
+        self.active = active;
+        expired
+    }
+
+    pub fn list(&self) -> &[Override] {
+        &self.active
+    }
+}
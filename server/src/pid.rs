@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+// Output has to move this far past the middle between two levels before the level changes
+const QUANTIZATION_HYSTERESIS: f32 = 0.1;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq)]
+pub struct TargetSettings {
+    pub target_temperature: u8,  // Desired APU temperature in °C
+    pub kp: f32,                 // Fan levels per °C of error
+    pub ki: f32,                 // Fan levels per °C of error per second
+    pub kd: f32,                 // Fan levels per °C/s of temperature change
+    pub min_level: u8,
+    pub max_level: u8,
+}
+
+impl Default for TargetSettings {
+    fn default() -> Self {
+        TargetSettings {
+            target_temperature: 75,
+            kp: 0.3,
+            ki: 0.02,
+            kd: 0.0,
+            min_level: 0,
+            max_level: 5,
+        }
+    }
+}
+
+impl TargetSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.target_temperature > 100 {
+            return Err("Target temperature must be 0-100°C".to_string());
+        }
+
+        for (name, gain) in [("kp", self.kp), ("ki", self.ki), ("kd", self.kd)] {
+            if !gain.is_finite() || gain < 0.0 {
+                return Err(format!("{} must be a non-negative number", name));
+            }
+        }
+
+        if self.max_level > 5 || self.min_level > self.max_level {
+            return Err("Level limits must satisfy 0 <= min_level <= max_level <= 5".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PidController {
+    integral: f32,
+    last_temperature: Option<f32>,
+}
+
+impl PidController {
+    // Start from the given level without a jump in output (bumpless transfer)
+    pub fn reset(&mut self, settings: &TargetSettings, current_level: u8) {
+        self.last_temperature = None;
+        self.integral = if settings.ki > 0.0 {
+            current_level as f32 / settings.ki
+        } else {
+            0.0
+        };
+    }
+
+    // Advance the controller by dt seconds and return the continuous output in fan levels
+    pub fn update(&mut self, settings: &TargetSettings, temperature: u8, dt: f32) -> f32 {
+        let min = settings.min_level as f32;
+        let max = settings.max_level as f32;
+        let temperature = temperature as f32;
+        let error = temperature - settings.target_temperature as f32;
+
+        // Derivative on measurement, so changing the target doesn't kick the output
+        let derivative = match self.last_temperature {
+            Some(last) if dt > 0.0 => (temperature - last) / dt,
+            _ => 0.0,
+        };
+        self.last_temperature = Some(temperature);
+
+        let proportional = settings.kp * error;
+        let differential = settings.kd * derivative;
+
+        // Anti-windup: only integrate while it doesn't push a saturated output further out
+        let candidate = self.integral + error * dt;
+        let unclamped = proportional + settings.ki * candidate + differential;
+        let winding_up = (unclamped > max && error > 0.0) || (unclamped < min && error < 0.0);
+        if !winding_up {
+            self.integral = candidate;
+        }
+
+        // The integral term alone never needs to exceed the output range
+        if settings.ki > 0.0 {
+            self.integral = self.integral.clamp(min / settings.ki, max / settings.ki);
+        } else {
+            self.integral = 0.0;
+        }
+
+        (proportional + settings.ki * self.integral + differential).clamp(min, max)
+    }
+}
+
+// Map the continuous controller output to one of the EC's discrete levels
+pub fn quantize(output: f32, current_level: u8, settings: &TargetSettings) -> u8 {
+    let current = current_level as f32;
+    let level = if (output - current).abs() > 0.5 + QUANTIZATION_HYSTERESIS {
+        output.round() as u8
+    } else {
+        current_level
+    };
+
+    level.clamp(settings.min_level, settings.max_level)
+}
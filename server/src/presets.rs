@@ -0,0 +1,70 @@
+// Named curve presets: built-in ones plus user-defined ones from config
+
+use std::collections::BTreeMap;
+
+use crate::curve::{self, FanCurveSet};
+
+// Default curves of the Linux driver for fans 1 and 2
+pub const LINUX_DEFAULT: FanCurveSet = FanCurveSet {
+    rampup_curve: [60, 70, 83, 95, 97],
+    rampdown_curve: [40, 50, 80, 94, 96],
+};
+
+// Fan3 has different default curves in the Linux driver
+pub const LINUX_FAN3_DEFAULT: FanCurveSet = FanCurveSet {
+    rampup_curve: [20, 60, 83, 95, 97],
+    rampdown_curve: [0, 50, 80, 94, 96],
+};
+
+pub const BUILTIN_PRESETS: [(&str, FanCurveSet); 5] = [
+    ("silent", FanCurveSet {
+        rampup_curve: [70, 80, 88, 95, 97],
+        rampdown_curve: [55, 65, 85, 94, 96],
+    }),
+    ("balanced", FanCurveSet {
+        rampup_curve: [60, 70, 80, 90, 95],
+        rampdown_curve: [45, 55, 75, 88, 93],
+    }),
+    ("aggressive", FanCurveSet {
+        rampup_curve: [45, 55, 65, 75, 85],
+        rampdown_curve: [35, 45, 55, 65, 75],
+    }),
+    ("linux_default", LINUX_DEFAULT),
+    ("linux_fan3_default", LINUX_FAN3_DEFAULT),
+];
+
+// Curves a fan starts with when nothing else is configured
+pub fn default_curves(fan_id: u8) -> FanCurveSet {
+    if fan_id == 3 {
+        LINUX_FAN3_DEFAULT
+    } else {
+        LINUX_DEFAULT
+    }
+}
+
+pub fn builtin(name: &str) -> Option<FanCurveSet> {
+    BUILTIN_PRESETS.iter()
+        .find(|(preset_name, _)| *preset_name == name)
+        .map(|(_, set)| *set)
+}
+
+pub fn validate_user_preset(name: &str, set: &FanCurveSet) -> Result<(), String> {
+    if name.is_empty() || name.len() > 64 {
+        return Err("Preset name must be 1-64 characters long".to_string());
+    }
+    if builtin(name).is_some() {
+        return Err(format!("Preset name {} is reserved for a built-in preset", name));
+    }
+    curve::validate_curve(&set.rampup_curve)?;
+    curve::validate_curve(&set.rampdown_curve)?;
+    Ok(())
+}
+
+// Built-in presets always win, so the defaults can't be shadowed by config
+pub fn find(name: &str, user_presets: &BTreeMap<String, FanCurveSet>) -> Option<FanCurveSet> {
+    builtin(name).or_else(|| {
+        user_presets.get(name)
+            .filter(|set| validate_user_preset(name, set).is_ok())
+            .copied()
+    })
+}
@@ -0,0 +1,114 @@
+// Rules that apply a profile while certain executables are running
+
+use std::collections::HashSet;
+use std::mem;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
+use winapi::um::tlhelp32::{CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W, TH32CS_SNAPPROCESS};
+
+// How long a rule's processes have to be running (or gone) before the rule switches
+pub const DEFAULT_PROCESS_DEBOUNCE_SECONDS: u64 = 5;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct ProcessRule {
+    pub processes: Vec<String>,  // Executable names like "cl.exe", any of them triggers the rule
+    pub profile: String,
+    #[serde(default)]
+    pub priority: i32,           // Highest wins when several rules match, earlier rules win ties
+}
+
+impl ProcessRule {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.processes.is_empty() {
+            return Err("Process rules need at least one process".to_string());
+        }
+        if self.processes.iter().any(|name| name.is_empty()) {
+            return Err("Process names must not be empty".to_string());
+        }
+        Ok(())
+    }
+
+    fn matches(&self, running: &HashSet<String>) -> bool {
+        self.processes.iter().any(|name| running.contains(&name.to_lowercase()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct RuleState {
+    active: bool,
+    pending_since: Option<Instant>,  // When the raw match first differed from `active`
+}
+
+// Debounced match state of each rule, reset whenever the rules change
+#[derive(Debug, Default)]
+pub struct ProcessWatcher {
+    rules: Vec<ProcessRule>,
+    states: Vec<RuleState>,
+}
+
+impl ProcessWatcher {
+    pub fn update(&mut self, rules: &[ProcessRule], running: &HashSet<String>, debounce: Duration, now: Instant) {
+        if self.rules != rules {
+            self.rules = rules.to_vec();
+            self.states = vec![RuleState::default(); rules.len()];
+        }
+
+        for (rule, state) in self.rules.iter().zip(self.states.iter_mut()) {
+            if rule.matches(running) == state.active {
+                state.pending_since = None;
+                continue;
+            }
+
+            let since = *state.pending_since.get_or_insert(now);
+            if now.duration_since(since) >= debounce {
+                state.active = !state.active;
+                state.pending_since = None;
+            }
+        }
+    }
+
+    pub fn rules(&self) -> &[ProcessRule] {
+        &self.rules
+    }
+
+    pub fn matched(&self) -> Vec<bool> {
+        self.states.iter().map(|state| state.active).collect()
+    }
+
+    pub fn active(&self) -> Option<(usize, &ProcessRule)> {
+        self.rules.iter()
+            .enumerate()
+            .zip(&self.states)
+            .filter(|(_, state)| state.active)
+            .map(|(rule, _)| rule)
+            .fold(None, |best: Option<(usize, &ProcessRule)>, (index, rule)| match best {
+                Some((_, best_rule)) if best_rule.priority >= rule.priority => best,
+                _ => Some((index, rule)),
+            })
+    }
+}
+
+// Lowercase executable names of all running processes
+pub fn running_processes() -> Result<HashSet<String>, String> {
+    let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) };
+    if snapshot == INVALID_HANDLE_VALUE {
+        return Err("Failed to create process snapshot".to_string());
+    }
+
+    let mut processes = HashSet::new();
+    let mut entry: PROCESSENTRY32W = unsafe { mem::zeroed() };
+    entry.dwSize = mem::size_of::<PROCESSENTRY32W>() as u32;
+
+    let mut found = unsafe { Process32FirstW(snapshot, &mut entry) } != 0;
+    while found {
+        let len = entry.szExeFile.iter().position(|&c| c == 0).unwrap_or(entry.szExeFile.len());
+        processes.insert(String::from_utf16_lossy(&entry.szExeFile[..len]).to_lowercase());
+        found = unsafe { Process32NextW(snapshot, &mut entry) } != 0;
+    }
+
+    unsafe { CloseHandle(snapshot) };
+    Ok(processes)
+}
@@ -0,0 +1,94 @@
+// Named snapshots of the power mode and all fan settings, applied in one go
+
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+use crate::curve;
+use crate::ec::{FanMode, POWER_MODES};
+use crate::limits::FanLimits;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct FanProfile {
+    pub mode: String,
+    pub level: u8,               // Only used in fixed mode
+    pub rampup_curve: [u8; 5],
+    pub rampdown_curve: [u8; 5],
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<FanLimits>,  // The fan's current limits are kept if not set
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct Profile {
+    pub power_mode: String,
+    pub fan1: FanProfile,
+    pub fan2: FanProfile,
+    pub fan3: FanProfile,
+}
+
+impl FanProfile {
+    pub fn validate(&self) -> Result<(), String> {
+        FanMode::from_str(&self.mode)
+            .ok_or_else(|| format!("Invalid fan mode: {}", self.mode))?;
+        if self.level > 5 {
+            return Err("Fan level must be 0-5".to_string());
+        }
+        curve::validate_curve(&self.rampup_curve)?;
+        curve::validate_curve(&self.rampdown_curve)?;
+        if let Some(ref limits) = self.limits {
+            limits.validate()?;
+        }
+        Ok(())
+    }
+
+    // Compare only what takes effect in the fan's mode: the level in fixed mode, the curves in curve mode.
+    // Limits only count if the profile sets them.
+    fn matches(&self, state: &FanProfile) -> bool {
+        if self.mode != state.mode || self.limits.is_some_and(|limits| state.limits != Some(limits)) {
+            return false;
+        }
+        match self.mode.as_str() {
+            "fixed" => self.level == state.level,
+            "curve" => self.rampup_curve == state.rampup_curve && self.rampdown_curve == state.rampdown_curve,
+            _ => true,
+        }
+    }
+}
+
+impl Profile {
+    pub fn fans(&self) -> [&FanProfile; 3] {
+        [&self.fan1, &self.fan2, &self.fan3]
+    }
+
+    pub fn fans_mut(&mut self) -> [&mut FanProfile; 3] {
+        [&mut self.fan1, &mut self.fan2, &mut self.fan3]
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !POWER_MODES.contains(&self.power_mode.as_str()) {
+            return Err(format!("Invalid power mode: {}", self.power_mode));
+        }
+        for (fan_idx, fan) in self.fans().iter().enumerate() {
+            fan.validate().map_err(|e| format!("Fan{}: {}", fan_idx + 1, e))?;
+        }
+        Ok(())
+    }
+
+    pub fn matches(&self, state: &Profile) -> bool {
+        self.power_mode == state.power_mode
+            && self.fans().iter().zip(state.fans()).all(|(fan, state)| fan.matches(state))
+    }
+}
+
+pub fn validate_profile_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > 64 {
+        return Err("Profile name must be 1-64 characters long".to_string());
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(format!("Invalid profile name: {} (allowed: letters, digits, '_' and '-')", name));
+    }
+    // Taken by GET /profiles/active
+    if name == "active" {
+        return Err("Profile name active is reserved".to_string());
+    }
+    Ok(())
+}
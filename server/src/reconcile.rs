@@ -0,0 +1,118 @@
+// Periodic comparison of what the server last wrote with the actual EC registers, which can change behind
+// the server's back after resume from sleep or when the BIOS or a hotkey changes them
+
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+// Drift events kept for GET /drift
+const MAX_DRIFT_EVENTS: usize = 100;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftPolicy {
+    Enforce,  // Write the expected value again
+    Adopt,    // Take over the actual value as the expected one
+    Alert,    // Only log and report the drift
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq)]
+pub struct ReconcileSettings {
+    #[serde(default = "default_interval_seconds")]
+    pub interval_seconds: u64,  // 0 disables the reconciler
+    #[serde(default = "default_power_mode_policy")]
+    pub power_mode: DriftPolicy,
+    #[serde(default = "default_fan_mode_policy")]
+    pub fan1_mode: DriftPolicy,
+    #[serde(default = "default_fan_mode_policy")]
+    pub fan2_mode: DriftPolicy,
+    #[serde(default = "default_fan_mode_policy")]
+    pub fan3_mode: DriftPolicy,
+}
+
+fn default_interval_seconds() -> u64 {
+    5
+}
+
+// Power mode hotkeys are meant to work, so their changes are taken over by default
+fn default_power_mode_policy() -> DriftPolicy {
+    DriftPolicy::Adopt
+}
+
+// Fans dropping back to auto mode after sleep is never intended
+fn default_fan_mode_policy() -> DriftPolicy {
+    DriftPolicy::Enforce
+}
+
+impl Default for ReconcileSettings {
+    fn default() -> Self {
+        ReconcileSettings {
+            interval_seconds: default_interval_seconds(),
+            power_mode: default_power_mode_policy(),
+            fan1_mode: default_fan_mode_policy(),
+            fan2_mode: default_fan_mode_policy(),
+            fan3_mode: default_fan_mode_policy(),
+        }
+    }
+}
+
+impl ReconcileSettings {
+    pub fn fan_mode(&self, fan_id: u8) -> DriftPolicy {
+        match fan_id {
+            1 => self.fan1_mode,
+            2 => self.fan2_mode,
+            _ => self.fan3_mode,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct DriftEvent {
+    pub time: DateTime<Utc>,
+    pub setting: String,   // "power_mode" or "fan1_mode"-"fan3_mode"
+    pub expected: String,
+    pub actual: String,
+    pub policy: DriftPolicy,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,  // Set if the policy couldn't be carried out
+}
+
+#[derive(Debug, Default)]
+pub struct DriftLog {
+    events: VecDeque<DriftEvent>,
+    unresolved: HashMap<String, String>,  // Actual value of drifting settings that were left as they are
+}
+
+impl DriftLog {
+    // Returns the events worth logging. A drift that is only alerted, or that couldn't be fixed, is found again
+    // on every run, so it's reported once until it goes away or changes.
+    pub fn record(&mut self, drifts: Vec<DriftEvent>) -> Vec<DriftEvent> {
+        let mut unresolved = HashMap::new();
+        let mut recorded = Vec::new();
+
+        for drift in drifts {
+            if drift.policy == DriftPolicy::Alert || drift.error.is_some() {
+                let seen = self.unresolved.get(&drift.setting) == Some(&drift.actual);
+                unresolved.insert(drift.setting.clone(), drift.actual.clone());
+                if seen {
+                    continue;
+                }
+            }
+
+            if self.events.len() == MAX_DRIFT_EVENTS {
+                self.events.pop_front();
+            }
+            self.events.push_back(drift.clone());
+            recorded.push(drift);
+        }
+
+        self.unresolved = unresolved;
+        recorded
+    }
+
+    pub fn events(&self) -> Vec<DriftEvent> {
+        self.events.iter().cloned().collect()
+    }
+}
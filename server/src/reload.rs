@@ -0,0 +1,102 @@
+// Applying a new config while the server is running, from the config file edited by hand or from PUT /config
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::{FanConfig, ServerConfig};
+use crate::ec::EcOperation;
+
+// Result of the last reload, for GET /status
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReloadStatus {
+    pub time: DateTime<Utc>,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,     // Why the file was rejected or couldn't be applied, the running config stays as it is then
+    pub changes: Vec<String>,      // Top level settings that changed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failed: Vec<String>,       // Setting that couldn't be written to the EC
+}
+
+// Top level setting with a different value, e.g. "profiles" or "fan2"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConfigChange {
+    pub setting: String,
+    pub current: Value,
+    pub new: Value,
+}
+
+pub fn diff(old: &ServerConfig, new: &ServerConfig) -> Vec<ConfigChange> {
+    let old = serde_json::to_value(old).unwrap_or_default();
+    let new = serde_json::to_value(new).unwrap_or_default();
+    match (old.as_object(), new.as_object()) {
+        (Some(old), Some(new)) => new.iter()
+            .filter(|(key, value)| old.get(key.as_str()) != Some(*value))
+            .map(|(key, value)| ConfigChange {
+                setting: key.clone(),
+                current: old.get(key.as_str()).cloned().unwrap_or(Value::Null),
+                new: value.clone(),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+pub fn changed_settings(old: &ServerConfig, new: &ServerConfig) -> Vec<String> {
+    diff(old, new).into_iter().map(|change| change.setting).collect()
+}
+
+// EC writes for the changed power and fan settings, in the same order as restoring on startup
+pub fn ec_operations(old: &ServerConfig, new: &ServerConfig) -> Vec<(String, EcOperation)> {
+    let mut operations = Vec::new();
+
+    if new.apu_power_mode != old.apu_power_mode {
+        if let Some(ref power_mode) = new.apu_power_mode {
+            operations.push(("power_mode".to_string(), EcOperation::SetApuPowerMode(power_mode.clone())));
+        }
+    }
+
+    let old_fans = [&old.fan1, &old.fan2, &old.fan3];
+    let new_fans = [&new.fan1, &new.fan2, &new.fan3];
+    for (fan_idx, (old_fan, new_fan)) in old_fans.iter().zip(new_fans).enumerate() {
+        // A fan removed from config is left in its current state, like on startup
+        if let Some(new_fan) = new_fan {
+            fan_operations(fan_idx as u8 + 1, old_fan.as_ref(), new_fan, &mut operations);
+        }
+    }
+
+    operations
+}
+
+fn fan_operations(fan_id: u8, old: Option<&FanConfig>, new: &FanConfig, operations: &mut Vec<(String, EcOperation)>) {
+    let fan = format!("fan{}", fan_id);
+    let changed = |same: fn(&FanConfig, &FanConfig) -> bool| old.is_none_or(|old| !same(old, new));
+
+    if changed(|a, b| a.limits == b.limits) {
+        operations.push((format!("{}_limits", fan), EcOperation::SetFanLimits(fan_id, new.limits)));
+    }
+    if changed(|a, b| a.target == b.target) {
+        operations.push((format!("{}_target", fan), EcOperation::SetFanTarget(fan_id, new.target)));
+    }
+    if changed(|a, b| a.temperature_source == b.temperature_source) {
+        operations.push((format!("{}_temperature_source", fan), EcOperation::SetFanTemperatureSource(fan_id, new.temperature_source.clone())));
+    }
+    if changed(|a, b| a.power_mode_curves == b.power_mode_curves) {
+        operations.push((format!("{}_power_mode_curves", fan), EcOperation::SetFanPowerModeCurves(fan_id, new.power_mode_curves.clone())));
+    }
+    if changed(|a, b| a.rampup_curve == b.rampup_curve) {
+        operations.push((format!("{}_rampup_curve", fan), EcOperation::SetFanRampupCurve(fan_id, new.rampup_curve)));
+    }
+    if changed(|a, b| a.rampdown_curve == b.rampdown_curve) {
+        operations.push((format!("{}_rampdown_curve", fan), EcOperation::SetFanRampdownCurve(fan_id, new.rampdown_curve)));
+    }
+
+    let mode_changed = changed(|a, b| a.mode == b.mode);
+    if mode_changed {
+        operations.push((format!("{}_mode", fan), EcOperation::SetFanMode(fan_id, new.mode.clone())));
+    }
+    if new.mode == "fixed" && (mode_changed || changed(|a, b| a.level == b.level)) {
+        operations.push((format!("{}_level", fan), EcOperation::SetFanLevel(fan_id, new.level)));
+    }
+}
@@ -0,0 +1,187 @@
+// Restoring the saved configuration to the EC on startup, step by step and in a fixed order
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+use crate::config::{FanConfig, ServerConfig};
+use crate::ec::{EcController, EcOperation, EcResult};
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RestorePolicy {
+    #[default]
+    All,   // Write every saved setting
+    None,  // Leave the EC as it is
+    Diff,  // Only write settings that differ from the current state
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Ok,
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RestoreStep {
+    pub step: String,
+    pub status: StepStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,  // Error, reason for skipping, or how a level was clamped
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RestoreReport {
+    pub policy: RestorePolicy,
+    pub finished: Option<DateTime<Utc>>,
+    pub steps: Vec<RestoreStep>,
+}
+
+impl RestoreReport {
+    pub fn record(&mut self, step: &str, status: StepStatus, detail: Option<String>) {
+        match (status, &detail) {
+            (StepStatus::Ok, Some(note)) => log::warn!("Restored {}, {}", step, note),
+            (StepStatus::Ok, None) => log::info!("Restored {}", step),
+            (StepStatus::Skipped, Some(reason)) => log::info!("Skipped restoring {}: {}", step, reason),
+            (StepStatus::Skipped, None) => log::info!("Skipped restoring {}", step),
+            (StepStatus::Failed, Some(e)) => log::warn!("Failed to restore {}: {}", step, e),
+            (StepStatus::Failed, None) => log::warn!("Failed to restore {}", step),
+        }
+
+        self.steps.push(RestoreStep {
+            step: step.to_string(),
+            status,
+            detail,
+        });
+    }
+
+    pub fn failed(&self) -> usize {
+        self.steps.iter().filter(|step| step.status == StepStatus::Failed).count()
+    }
+}
+
+struct Restorer<'a> {
+    ec: &'a EcController,
+    report: RestoreReport,
+}
+
+impl Restorer<'_> {
+    // In diff mode the current value is read first and the step is skipped if it already matches
+    async fn step(&mut self, step: &str, read: EcOperation, matches: impl Fn(&EcResult) -> bool, write: EcOperation) -> StepStatus {
+        if self.report.policy == RestorePolicy::Diff {
+            if let Ok(current) = self.ec.execute_operation(read).await {
+                if matches(&current) {
+                    self.report.record(step, StepStatus::Skipped, Some("already set".to_string()));
+                    return StepStatus::Skipped;
+                }
+            }
+        }
+
+        let (status, detail) = match self.ec.execute_operation(write).await {
+            Ok(EcResult::FanLevelSet { clamp: Some(clamp), .. }) => {
+                (StepStatus::Ok, Some(format!("level {} clamped to {}: {}", clamp.requested_level, clamp.level, clamp.reason)))
+            }
+            Ok(_) => (StepStatus::Ok, None),
+            Err(e) => (StepStatus::Failed, Some(e)),
+        };
+        self.report.record(step, status, detail);
+        status
+    }
+
+    // Settings that only live in the server are loaded whatever the policy, it only decides about EC registers
+    async fn load(&mut self, step: &str, write: EcOperation) {
+        let (status, detail) = match self.ec.execute_operation(write).await {
+            Ok(_) => (StepStatus::Ok, None),
+            Err(e) => (StepStatus::Failed, Some(e)),
+        };
+        self.report.record(step, status, detail);
+    }
+
+    fn skip_register(&mut self, step: &str) {
+        self.report.record(step, StepStatus::Skipped, Some("restore policy is none".to_string()));
+    }
+
+    // Settings that only live in the server come first, they decide what the mode and level steps do:
+    // limits before any level is written, curves and temperature source before curve mode picks its
+    // initial level, target settings before target mode starts. The level only matters in fixed mode.
+    async fn restore_fan(&mut self, fan_id: u8, fan_config: &FanConfig) {
+        let fan = format!("fan{}", fan_id);
+
+        self.load(&format!("{}_limits", fan), EcOperation::SetFanLimits(fan_id, fan_config.limits)).await;
+        self.load(&format!("{}_target", fan), EcOperation::SetFanTarget(fan_id, fan_config.target)).await;
+        self.load(&format!("{}_temperature_source", fan),
+            EcOperation::SetFanTemperatureSource(fan_id, fan_config.temperature_source.clone())).await;
+        self.load(&format!("{}_power_mode_curves", fan),
+            EcOperation::SetFanPowerModeCurves(fan_id, fan_config.power_mode_curves.clone())).await;
+        self.load(&format!("{}_rampup_curve", fan), EcOperation::SetFanRampupCurve(fan_id, fan_config.rampup_curve)).await;
+        self.load(&format!("{}_rampdown_curve", fan), EcOperation::SetFanRampdownCurve(fan_id, fan_config.rampdown_curve)).await;
+
+        if self.report.policy == RestorePolicy::None {
+            self.skip_register(&format!("{}_mode", fan));
+            self.skip_register(&format!("{}_level", fan));
+            return;
+        }
+
+        let mode = &fan_config.mode;
+        let mode_status = self.step(&format!("{}_mode", fan), EcOperation::GetFanMode(fan_id),
+            |r| matches!(r, EcResult::FanMode(current) if current == mode),
+            EcOperation::SetFanMode(fan_id, mode.clone())).await;
+
+        // The EC already had the mode, the server still has to know it's the one to keep
+        if mode_status == StepStatus::Skipped {
+            let _ = self.ec.execute_operation(EcOperation::AdoptFanMode(fan_id, mode.clone())).await;
+        }
+
+        let level_step = format!("{}_level", fan);
+        if mode != "fixed" {
+            self.report.record(&level_step, StepStatus::Skipped, Some(format!("not used in {} mode", mode)));
+        } else if mode_status == StepStatus::Failed {
+            self.report.record(&level_step, StepStatus::Skipped, Some("mode wasn't restored".to_string()));
+        } else {
+            let level = fan_config.level;
+            self.step(&level_step, EcOperation::GetFanLevel(fan_id),
+                |r| matches!(r, EcResult::FanLevel(current) if *current == level),
+                EcOperation::SetFanLevel(fan_id, level)).await;
+        }
+    }
+}
+
+// Power mode first, curve sets and the curve engine depend on it, then fans 1-3
+pub async fn restore_config(ec: &EcController, config: &ServerConfig) -> RestoreReport {
+    let mut restorer = Restorer {
+        ec,
+        report: RestoreReport {
+            policy: config.restore_policy,
+            ..RestoreReport::default()
+        },
+    };
+
+    if config.restore_policy == RestorePolicy::None {
+        log::info!("Restore policy is none, leaving the EC registers as they are");
+    }
+
+    match config.apu_power_mode {
+        Some(_) if config.restore_policy == RestorePolicy::None => restorer.skip_register("power_mode"),
+        Some(ref power_mode) => {
+            restorer.step("power_mode", EcOperation::GetApuPowerMode,
+                |r| matches!(r, EcResult::ApuPowerMode(current) if current == power_mode),
+                EcOperation::SetApuPowerMode(power_mode.clone())).await;
+        }
+        None => restorer.report.record("power_mode", StepStatus::Skipped, Some("not saved in config".to_string())),
+    }
+
+    let fan_configs = [&config.fan1, &config.fan2, &config.fan3];
+    for (fan_idx, fan_config) in fan_configs.iter().enumerate() {
+        let fan_id = fan_idx as u8 + 1;
+        match fan_config {
+            Some(fan_config) => restorer.restore_fan(fan_id, fan_config).await,
+            None => restorer.report.record(&format!("fan{}", fan_id), StepStatus::Skipped,
+                Some("not saved in config, left in original state".to_string())),
+        }
+    }
+
+    restorer.report.finished = Some(Utc::now());
+    restorer.report
+}
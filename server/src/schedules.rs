@@ -0,0 +1,113 @@
+// Time-of-day schedules that apply a profile while their window is active
+
+use chrono::{Datelike, Duration, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+// Windows are checked at most this many days ahead when looking for the next boundary
+const BOUNDARY_LOOKAHEAD_DAYS: i64 = 7;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct Schedule {
+    pub profile: String,
+    #[serde(default)]
+    pub days: Vec<String>,  // Days the window starts on ("mon"-"sun"), every day if empty
+    pub start: String,      // Local time "HH:MM"
+    pub end: String,        // Local time "HH:MM", earlier than start for windows over midnight
+}
+
+impl Schedule {
+    pub fn validate(&self) -> Result<(), String> {
+        for day in &self.days {
+            if !WEEKDAYS.contains(&day.as_str()) {
+                return Err(format!("Invalid day: {} (allowed: {})", day, WEEKDAYS.join(", ")));
+            }
+        }
+
+        let (start, end) = self.times()?;
+        if start == end {
+            return Err("Schedule start and end must differ".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn times(&self) -> Result<(NaiveTime, NaiveTime), String> {
+        Ok((parse_time(&self.start)?, parse_time(&self.end)?))
+    }
+
+    fn runs_on(&self, day: Weekday) -> bool {
+        self.days.is_empty()
+            || self.days.iter().any(|d| d == WEEKDAYS[day.num_days_from_monday() as usize])
+    }
+
+    pub fn is_active(&self, now: NaiveDateTime) -> bool {
+        let Ok((start, end)) = self.times() else {
+            return false;
+        };
+        let time = now.time();
+        let today = now.weekday();
+
+        if start < end {
+            self.runs_on(today) && time >= start && time < end
+        } else {
+            // Window over midnight belongs to the day it starts on
+            (self.runs_on(today) && time >= start) || (self.runs_on(today.pred()) && time < end)
+        }
+    }
+
+    fn next_boundary(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let (start, end) = self.times().ok()?;
+
+        // Start from yesterday, its window may still be running
+        (-1..=BOUNDARY_LOOKAHEAD_DAYS)
+            .map(|offset| now.date() + Duration::days(offset))
+            .filter(|date| self.runs_on(date.weekday()))
+            .flat_map(|date| {
+                let end_date = if end > start { date } else { date + Duration::days(1) };
+                [date.and_time(start), end_date.and_time(end)]
+            })
+            .filter(|boundary| *boundary > now)
+            .min()
+    }
+}
+
+// Profile applied by hand in place of the schedules, held until the next schedule boundary
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScheduleOverride {
+    pub profile: String,
+    pub until: Option<NaiveDateTime>,  // Local time, None if no schedule has a boundary ahead
+}
+
+impl ScheduleOverride {
+    pub fn new(profile: String, schedules: &[Schedule], now: NaiveDateTime) -> Self {
+        ScheduleOverride {
+            profile,
+            until: next_boundary(schedules, now),
+        }
+    }
+
+    pub fn has_ended(&self, now: NaiveDateTime) -> bool {
+        self.until.is_some_and(|until| now >= until)
+    }
+}
+
+// Earlier schedules win when windows overlap
+pub fn active(schedules: &[Schedule], now: NaiveDateTime) -> Option<(usize, &Schedule)> {
+    schedules.iter()
+        .enumerate()
+        .find(|(_, schedule)| schedule.is_active(now))
+}
+
+pub fn next_boundary(schedules: &[Schedule], now: NaiveDateTime) -> Option<NaiveDateTime> {
+    schedules.iter()
+        .filter_map(|schedule| schedule.next_boundary(now))
+        .min()
+}
+
+fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time, "%H:%M")
+        .map_err(|_| format!("Invalid time: {} (expected HH:MM)", time))
+}
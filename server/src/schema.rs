@@ -0,0 +1,69 @@
+// JSON Schemas generated from the types the server reads, for validating config files and request bodies
+// before sending them
+
+use std::collections::BTreeMap;
+
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+
+use crate::config::ServerConfig;
+use crate::curve::FanCurveSet;
+use crate::limits::FanLimits;
+use crate::logger::LogLevels;
+use crate::process_rules::ProcessRule;
+use crate::profiles::Profile;
+use crate::schedules::Schedule;
+use crate::sensors::TemperatureSource;
+use crate::{
+    CurvePresetApplyRequest, FanCurveRequest, FanCurveSimulationRequest, FanLevelRequest, FanModeRequest,
+    FanTargetRequest, PowerModeRequest, ScheduleOverrideRequest, SensorPushRequest,
+};
+
+// Names served under /schema/, "config" is config.json and PUT /config, the rest are request bodies
+pub const SCHEMA_NAMES: [&str; 17] = [
+    "config",
+    "PowerModeRequest",
+    "FanModeRequest",
+    "FanLevelRequest",
+    "FanCurveRequest",
+    "FanTargetRequest",
+    "FanLimits",
+    "FanPowerModeCurves",
+    "TemperatureSource",
+    "SensorPushRequest",
+    "FanCurveSimulationRequest",
+    "CurvePresetApplyRequest",
+    "Profile",
+    "Schedules",
+    "ScheduleOverrideRequest",
+    "ProcessRules",
+    "LogLevels",
+];
+
+pub fn config_schema() -> RootSchema {
+    schema_for!(ServerConfig)
+}
+
+pub fn schema(name: &str) -> Option<RootSchema> {
+    let schema = match name {
+        "config" => config_schema(),
+        "PowerModeRequest" => schema_for!(PowerModeRequest),
+        "FanModeRequest" => schema_for!(FanModeRequest),
+        "FanLevelRequest" => schema_for!(FanLevelRequest),
+        "FanCurveRequest" => schema_for!(FanCurveRequest),
+        "FanTargetRequest" => schema_for!(FanTargetRequest),
+        "FanLimits" => schema_for!(FanLimits),
+        "FanPowerModeCurves" => schema_for!(BTreeMap<String, FanCurveSet>),
+        "TemperatureSource" => schema_for!(TemperatureSource),
+        "SensorPushRequest" => schema_for!(SensorPushRequest),
+        "FanCurveSimulationRequest" => schema_for!(FanCurveSimulationRequest),
+        "CurvePresetApplyRequest" => schema_for!(CurvePresetApplyRequest),
+        "Profile" => schema_for!(Profile),
+        "Schedules" => schema_for!(Vec<Schedule>),
+        "ScheduleOverrideRequest" => schema_for!(ScheduleOverrideRequest),
+        "ProcessRules" => schema_for!(Vec<ProcessRule>),
+        "LogLevels" => schema_for!(LogLevels),
+        _ => return None,
+    };
+    Some(schema)
+}
@@ -0,0 +1,94 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+// How long a pushed value stays valid if neither the push nor the config says otherwise
+pub const DEFAULT_SENSOR_EXPIRY_SECONDS: u64 = 30;
+
+// Longest expiry a pushed value may have (one day)
+pub const MAX_SENSOR_EXPIRY_SECONDS: u64 = 24 * 60 * 60;
+
+// Pushed sensors kept at once, values for more names are rejected
+pub const MAX_PUSHED_SENSORS: usize = 64;
+
+// How long a stale value is still listed before it's forgotten
+const STALE_SENSOR_RETENTION_SECONDS: i64 = 10 * 60;
+
+// Limits nesting of aggregates and fallbacks, so a source always resolves quickly
+const MAX_SOURCE_DEPTH: usize = 4;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TemperatureSource {
+    // APU temperature sensor of the EC
+    #[default]
+    Apu,
+    // Any other EC register holding a temperature in °C
+    EcRegister { register: u8 },
+    // Value pushed by an external agent via POST /sensors/{name}
+    Pushed {
+        name: String,
+        #[serde(default)]
+        fallback: Box<TemperatureSource>,
+    },
+    // Highest temperature of several sources
+    Max { sources: Vec<TemperatureSource> },
+    // Average temperature of several sources
+    Avg { sources: Vec<TemperatureSource> },
+}
+
+impl TemperatureSource {
+    pub fn validate(&self) -> Result<(), String> {
+        self.validate_depth(0)
+    }
+
+    fn validate_depth(&self, depth: usize) -> Result<(), String> {
+        if depth > MAX_SOURCE_DEPTH {
+            return Err(format!("Temperature sources can't be nested more than {} levels deep", MAX_SOURCE_DEPTH));
+        }
+
+        match self {
+            TemperatureSource::Apu | TemperatureSource::EcRegister { .. } => Ok(()),
+            TemperatureSource::Pushed { name, fallback } => {
+                validate_sensor_name(name)?;
+                fallback.validate_depth(depth + 1)
+            }
+            TemperatureSource::Max { sources } | TemperatureSource::Avg { sources } => {
+                if sources.is_empty() {
+                    return Err("Aggregate temperature sources need at least one source".to_string());
+                }
+                for source in sources {
+                    source.validate_depth(depth + 1)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PushedSensor {
+    pub temperature: u8,
+    pub updated: DateTime<Utc>,
+    pub expires: DateTime<Utc>,
+}
+
+impl PushedSensor {
+    pub fn is_stale(&self, now: DateTime<Utc>) -> bool {
+        now >= self.expires
+    }
+
+    pub fn is_forgotten(&self, now: DateTime<Utc>) -> bool {
+        now >= self.expires + chrono::Duration::seconds(STALE_SENSOR_RETENTION_SECONDS)
+    }
+}
+
+pub fn validate_sensor_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > 64 {
+        return Err("Sensor name must be 1-64 characters long".to_string());
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(format!("Invalid sensor name: {} (allowed: letters, digits, '_' and '-')", name));
+    }
+    Ok(())
+}